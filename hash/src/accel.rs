@@ -0,0 +1,182 @@
+// Runtime selection of the SHA-1 / SHA-256 / SHA-512 compression functions.
+// The fastest backend supported by the CPU is picked once and used for every block;
+// other CPUs and targets fall back to the portable implementation.
+
+use std::sync::OnceLock;
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod x86;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Portable,
+    // AVX2 message schedule with BMI2 rotations in the rounds (SHA-512)
+    Avx2,
+    // SHA extensions (SHA-NI, SHA-1 and SHA-256)
+    ShaNi,
+}
+
+impl Backend {
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Portable => true,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => {
+                std::arch::is_x86_feature_detected!("avx2")
+                    && std::arch::is_x86_feature_detected!("bmi2")
+            }
+            #[cfg(target_arch = "x86_64")]
+            Backend::ShaNi => {
+                std::arch::is_x86_feature_detected!("sha")
+                    && std::arch::is_x86_feature_detected!("sse4.1")
+                    && std::arch::is_x86_feature_detected!("ssse3")
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+}
+
+fn select(candidates: &[Backend]) -> Backend {
+    candidates
+        .iter()
+        .copied()
+        .find(|x| x.is_supported())
+        .unwrap_or(Backend::Portable)
+}
+
+// SHA-1 and SHA-256. The AVX2 schedule does not pay off next to their 32-bit rounds.
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+    *BACKEND.get_or_init(|| select(&[Backend::ShaNi]))
+}
+
+// SHA-512 and its truncations, which SHA-NI does not cover
+pub fn sha512_backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+    *BACKEND.get_or_init(|| select(&[Backend::Avx2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sha1::Sha1, sha256::Sha256, sha512::Sha512};
+
+    use super::{backend, sha512_backend, Backend};
+
+    fn blocks() -> Vec<[u8; 64]> {
+        // xorshift, so that the blocks cover all byte values
+        let mut x = 0x243f6a8885a308d3u64;
+        (0..64)
+            .map(|_| {
+                let mut block = [0; 64];
+                for b in block.iter_mut() {
+                    x ^= x << 13;
+                    x ^= x >> 7;
+                    x ^= x << 17;
+                    *b = x as u8;
+                }
+                block
+            })
+            .chain([[0; 64], [0xff; 64]])
+            .collect()
+    }
+
+    #[test]
+    fn accel_backend() {
+        assert!(backend().is_supported());
+        assert!(sha512_backend().is_supported());
+        assert!(Backend::Portable.is_supported());
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn accel_sha256() {
+        use super::x86;
+
+        if !Backend::ShaNi.is_supported() {
+            return;
+        }
+
+        let mut expected = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut hs = expected;
+        for block in blocks() {
+            Sha256::compress_portable(&mut expected, &block);
+            unsafe { x86::sha256_shani(&mut hs, &block) };
+            assert_eq!(hs, expected);
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn accel_sha1() {
+        use super::x86;
+
+        if !Backend::ShaNi.is_supported() {
+            return;
+        }
+
+        let mut expected = [1, 2, 3, 4, 5];
+        let mut hs = expected;
+        for block in blocks() {
+            Sha1::compress_portable(&mut expected, &block);
+            unsafe { x86::sha1_shani(&mut hs, &block) };
+            assert_eq!(hs, expected);
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn accel_sha512() {
+        use super::x86;
+
+        if !Backend::Avx2.is_supported() {
+            return;
+        }
+
+        let mut expected = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut hs = expected;
+        for block in blocks().chunks_exact(2) {
+            let block: [u8; 128] = [block[0], block[1]].concat().try_into().unwrap();
+            Sha512::compress_portable(&mut expected, &block);
+            unsafe { x86::sha512_avx2(&mut hs, &block) };
+            assert_eq!(hs, expected);
+        }
+    }
+
+    // cargo test --release accel_throughput -- --ignored --nocapture
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    #[ignore]
+    fn accel_throughput() {
+        use std::time::Instant;
+
+        use super::x86;
+
+        let blocks: Vec<[u8; 128]> = blocks()
+            .chunks_exact(2)
+            .map(|x| [x[0], x[1]].concat().try_into().unwrap())
+            .collect();
+        let rate = |compress: &dyn Fn(&mut [u64; 8], &[u8; 128])| {
+            let mut hs = Sha512::IV;
+            let t = Instant::now();
+            for _ in 0..2000 {
+                for block in &blocks {
+                    compress(&mut hs, block);
+                }
+            }
+            std::hint::black_box(hs);
+            (2000 * 128 * blocks.len()) as f64 / 1e6 / t.elapsed().as_secs_f64()
+        };
+
+        let portable = rate(&|hs, block| Sha512::compress_portable(hs, block));
+        if Backend::Avx2.is_supported() {
+            let avx2 = rate(&|hs, block| unsafe { x86::sha512_avx2(hs, block) });
+            println!(
+                "SHA-512: portable {:.0} MB/s, AVX2 {:.0} MB/s",
+                portable, avx2
+            );
+        }
+    }
+}
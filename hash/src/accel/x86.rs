@@ -0,0 +1,193 @@
+// x86_64 backends. Callers must check `Backend::is_supported` first.
+
+use std::arch::x86_64::*;
+
+use crate::{sha256::Sha256, sha512::Sha512};
+
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub(crate) unsafe fn sha256_shani(hs: &mut [u32; 8], block: &[u8; 64]) {
+    let mask = _mm_set_epi64x(0x0c0d0e0f08090a0b, 0x0405060700010203);
+
+    // (A, B, E, F) and (C, D, G, H) as expected by sha256rnds2
+    let tmp = _mm_loadu_si128(hs.as_ptr() as *const __m128i);
+    let state1 = _mm_loadu_si128(hs.as_ptr().add(4) as *const __m128i);
+    let tmp = _mm_shuffle_epi32::<0xb1>(tmp);
+    let state1 = _mm_shuffle_epi32::<0x1b>(state1);
+    let mut abef = _mm_alignr_epi8::<8>(tmp, state1);
+    let mut cdgh = _mm_blend_epi16::<0xf0>(state1, tmp);
+    let (abef_save, cdgh_save) = (abef, cdgh);
+
+    let mut w = [_mm_setzero_si128(); 4];
+    for (i, x) in w.iter_mut().enumerate() {
+        *x = _mm_shuffle_epi8(
+            _mm_loadu_si128(block.as_ptr().add(16 * i) as *const __m128i),
+            mask,
+        );
+    }
+
+    // four rounds per iteration, while expanding the message four words ahead
+    for i in 0..16 {
+        let k = _mm_loadu_si128(Sha256::K.as_ptr().add(4 * i) as *const __m128i);
+        let msg = _mm_add_epi32(w[i % 4], k);
+        cdgh = _mm_sha256rnds2_epu32(cdgh, abef, msg);
+        abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32::<0x0e>(msg));
+
+        if i < 12 {
+            let next = _mm_sha256msg1_epu32(w[i % 4], w[(i + 1) % 4]);
+            let next = _mm_add_epi32(next, _mm_alignr_epi8::<4>(w[(i + 3) % 4], w[(i + 2) % 4]));
+            w[i % 4] = _mm_sha256msg2_epu32(next, w[(i + 3) % 4]);
+        }
+    }
+
+    let abef = _mm_add_epi32(abef, abef_save);
+    let cdgh = _mm_add_epi32(cdgh, cdgh_save);
+
+    let tmp = _mm_shuffle_epi32::<0x1b>(abef);
+    let cdgh = _mm_shuffle_epi32::<0xb1>(cdgh);
+    _mm_storeu_si128(
+        hs.as_mut_ptr() as *mut __m128i,
+        _mm_blend_epi16::<0xf0>(tmp, cdgh),
+    );
+    _mm_storeu_si128(
+        hs.as_mut_ptr().add(4) as *mut __m128i,
+        _mm_alignr_epi8::<8>(cdgh, tmp),
+    );
+}
+
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub(crate) unsafe fn sha1_shani(hs: &mut [u32; 5], block: &[u8; 64]) {
+    let mask = _mm_set_epi64x(0x0001020304050607, 0x08090a0b0c0d0e0f);
+
+    let mut abcd = _mm_shuffle_epi32::<0x1b>(_mm_loadu_si128(hs.as_ptr() as *const __m128i));
+    let e_save = _mm_set_epi32(hs[4] as i32, 0, 0, 0);
+    let abcd_save = abcd;
+
+    let mut w = [_mm_setzero_si128(); 4];
+    for (i, x) in w.iter_mut().enumerate() {
+        *x = _mm_shuffle_epi8(
+            _mm_loadu_si128(block.as_ptr().add(16 * i) as *const __m128i),
+            mask,
+        );
+    }
+
+    // four rounds per iteration. `e` carries E + W for the next four rounds.
+    let mut e = _mm_add_epi32(e_save, w[0]);
+    let mut prev = abcd;
+    for i in 0..20 {
+        prev = abcd;
+        abcd = match i / 5 {
+            0 => _mm_sha1rnds4_epu32::<0>(abcd, e),
+            1 => _mm_sha1rnds4_epu32::<1>(abcd, e),
+            2 => _mm_sha1rnds4_epu32::<2>(abcd, e),
+            _ => _mm_sha1rnds4_epu32::<3>(abcd, e),
+        };
+
+        if i < 19 {
+            if i >= 3 {
+                let next = _mm_sha1msg1_epu32(w[(i + 1) % 4], w[(i + 2) % 4]);
+                let next = _mm_xor_si128(next, w[(i + 3) % 4]);
+                w[(i + 1) % 4] = _mm_sha1msg2_epu32(next, w[i % 4]);
+            }
+            e = _mm_sha1nexte_epu32(prev, w[(i + 1) % 4]);
+        }
+    }
+
+    let e = _mm_sha1nexte_epu32(prev, e_save);
+    let abcd = _mm_add_epi32(abcd, abcd_save);
+
+    _mm_storeu_si128(
+        hs.as_mut_ptr() as *mut __m128i,
+        _mm_shuffle_epi32::<0x1b>(abcd),
+    );
+    hs[4] = _mm_extract_epi32::<3>(e) as u32;
+}
+
+#[inline(always)]
+unsafe fn rotr_epi64<const R: i32, const L: i32>(x: __m256i) -> __m256i {
+    _mm256_or_si256(_mm256_srli_epi64::<R>(x), _mm256_slli_epi64::<L>(x))
+}
+
+#[inline(always)]
+unsafe fn s0_epi64(x: __m256i) -> __m256i {
+    _mm256_xor_si256(
+        _mm256_xor_si256(rotr_epi64::<1, 63>(x), rotr_epi64::<8, 56>(x)),
+        _mm256_srli_epi64::<7>(x),
+    )
+}
+
+#[inline(always)]
+unsafe fn s1_epi64(x: __m256i) -> __m256i {
+    _mm256_xor_si256(
+        _mm256_xor_si256(rotr_epi64::<19, 45>(x), rotr_epi64::<61, 3>(x)),
+        _mm256_srli_epi64::<6>(x),
+    )
+}
+
+// The message schedule is computed four words at a time in 256-bit registers, and W + K
+// is added ahead of the rounds. The rounds are scalar, with `rorx` for the rotations.
+#[target_feature(enable = "avx2,bmi2")]
+pub(crate) unsafe fn sha512_avx2(hs: &mut [u64; 8], block: &[u8; 128]) {
+    let mask = _mm256_set_epi64x(
+        0x08090a0b0c0d0e0f,
+        0x0001020304050607,
+        0x08090a0b0c0d0e0f,
+        0x0001020304050607,
+    );
+    let zero = _mm256_setzero_si256();
+
+    let mut w = [_mm256_setzero_si256(); 20];
+    for (i, x) in w.iter_mut().take(4).enumerate() {
+        *x = _mm256_shuffle_epi8(
+            _mm256_loadu_si256(block.as_ptr().add(32 * i) as *const __m256i),
+            mask,
+        );
+    }
+    for i in 4..20 {
+        // W[t-15..t-11] and W[t-7..t-3] straddle two registers
+        let w15 = _mm256_permute4x64_epi64::<0x39>(_mm256_blend_epi32::<0x03>(w[i - 4], w[i - 3]));
+        let w7 = _mm256_permute4x64_epi64::<0x39>(_mm256_blend_epi32::<0x03>(w[i - 2], w[i - 1]));
+        let x = _mm256_add_epi64(_mm256_add_epi64(w[i - 4], s0_epi64(w15)), w7);
+
+        // s1 of W[t-2] and W[t-1] goes to the low half, then s1 of the new W[t] and
+        // W[t+1] to the high half
+        let w2 = _mm256_permute4x64_epi64::<0xee>(w[i - 1]);
+        let x = _mm256_add_epi64(x, _mm256_blend_epi32::<0xf0>(s1_epi64(w2), zero));
+        let lo = _mm256_permute4x64_epi64::<0x44>(x);
+        w[i] = _mm256_add_epi64(x, _mm256_blend_epi32::<0xf0>(zero, s1_epi64(lo)));
+    }
+
+    let mut wk = [0u64; 80];
+    for (i, x) in w.iter().enumerate() {
+        let k = _mm256_loadu_si256(Sha512::K.as_ptr().add(4 * i) as *const __m256i);
+        _mm256_storeu_si256(
+            wk.as_mut_ptr().add(4 * i) as *mut __m256i,
+            _mm256_add_epi64(*x, k),
+        );
+    }
+
+    // eight rounds per iteration, renaming the variables instead of moving them
+    macro_rules! round {
+        ($a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $f:ident, $g:ident, $h:ident, $wk:expr) => {
+            let t1 = $h
+                .wrapping_add(Sha512::S1($e))
+                .wrapping_add(Sha512::ch($e, $f, $g))
+                .wrapping_add($wk);
+            $d = $d.wrapping_add(t1);
+            $h = t1.wrapping_add(Sha512::S0($a).wrapping_add(Sha512::maj($a, $b, $c)));
+        };
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *hs;
+    for wk in wk.chunks_exact(8) {
+        round!(a, b, c, d, e, f, g, h, wk[0]);
+        round!(h, a, b, c, d, e, f, g, wk[1]);
+        round!(g, h, a, b, c, d, e, f, wk[2]);
+        round!(f, g, h, a, b, c, d, e, wk[3]);
+        round!(e, f, g, h, a, b, c, d, wk[4]);
+        round!(d, e, f, g, h, a, b, c, wk[5]);
+        round!(c, d, e, f, g, h, a, b, wk[6]);
+        round!(b, c, d, e, f, g, h, a, wk[7]);
+    }
+    for (x, y) in hs.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *x = x.wrapping_add(y);
+    }
+}
@@ -0,0 +1,575 @@
+use std::thread;
+
+use crate::{
+    blake2::Blake2b,
+    digest::HashDigest,
+    error::HashError,
+    util::{available_threads, ct_eq, from_b64, to_b64},
+};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// 1 KiB memory block
+type Block = [u64; 128];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    // data-dependent memory access
+    Argon2d,
+    // data-independent memory access
+    Argon2i,
+    // Argon2i for the first half of the first pass, Argon2d afterwards
+    Argon2id,
+}
+
+impl Variant {
+    fn id(self) -> u32 {
+        match self {
+            Variant::Argon2d => 0,
+            Variant::Argon2i => 1,
+            Variant::Argon2id => 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Argon2d => "argon2d",
+            Variant::Argon2i => "argon2i",
+            Variant::Argon2id => "argon2id",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Variant::Argon2d, Variant::Argon2i, Variant::Argon2id]
+            .into_iter()
+            .find(|x| x.name() == name)
+    }
+}
+
+// position of the segment being filled
+#[derive(Clone, Copy)]
+struct Position {
+    pass: usize,
+    slice: usize,
+    lane: usize,
+}
+
+// Argon2 (RFC 9106), version 0x13
+#[derive(Clone)]
+pub struct Argon2 {
+    variant: Variant,
+    // memory size in KiB
+    m_cost: u32,
+    // number of passes
+    t_cost: u32,
+    // number of lanes
+    lanes: u32,
+    threads: usize,
+    secret: Vec<u8>,
+    ad: Vec<u8>,
+}
+
+impl Argon2 {
+    const VERSION: u32 = 0x13;
+    const SYNC_POINTS: usize = 4;
+    const ADDRESSES_IN_BLOCK: usize = 128;
+    // bytes
+    const BLOCK_SIZE: usize = 1024;
+    pub const MIN_SALT_SIZE: usize = 8;
+    pub const MIN_OUTPUT_SIZE: usize = 4;
+
+    // `m_cost` is the memory size in KiB. The lanes are filled on up to `lanes` threads.
+    pub fn new(variant: Variant, m_cost: u32, t_cost: u32, lanes: u32) -> Result<Self> {
+        if lanes == 0 || lanes > 0xffffff {
+            return Err(HashError::ValueError(
+                "number of lanes must be in 1..=2^24-1".into(),
+            ));
+        }
+        if t_cost == 0 {
+            return Err(HashError::ValueError(
+                "number of passes must be positive".into(),
+            ));
+        }
+        if (m_cost as u64) < 8 * lanes as u64 {
+            return Err(HashError::ValueError(format!(
+                "memory size must be at least 8 * lanes ({} KiB)",
+                8 * lanes as u64
+            )));
+        }
+
+        Ok(Self {
+            variant,
+            m_cost,
+            t_cost,
+            lanes,
+            threads: available_threads().min(lanes as usize),
+            secret: vec![],
+            ad: vec![],
+        })
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.clamp(1, self.lanes as usize);
+    }
+
+    // optional key K, kept apart from the stored hash (a "pepper")
+    pub fn set_secret(&mut self, secret: &[u8]) {
+        self.secret = secret.to_vec();
+    }
+
+    // optional associated data X
+    pub fn set_associated_data(&mut self, ad: &[u8]) {
+        self.ad = ad.to_vec();
+    }
+
+    // H' (RFC 9106 section 3.3): BLAKE2b extended to outputs longer than 64 bytes
+    fn hash_long(inputs: &[&[u8]], len: usize) -> Vec<u8> {
+        let blake2b = |inputs: &[&[u8]], len: usize| {
+            let mut hasher = Blake2b::new_keyed(&[], len).unwrap();
+            for x in inputs {
+                hasher.update(x);
+            }
+            hasher.finalize().digest_u8()
+        };
+
+        let len_bytes = (len as u32).to_le_bytes();
+        let mut all = vec![len_bytes.as_slice()];
+        all.extend_from_slice(inputs);
+        if len <= Blake2b::DIGEST_SIZE {
+            return blake2b(&all, len);
+        }
+
+        // the first 32 bytes of each V_i, then all of the last one
+        let r = len.div_ceil(32) - 2;
+        let mut v = blake2b(&all, 64);
+        let mut res = Vec::with_capacity(len);
+        res.extend_from_slice(&v[..32]);
+        for _ in 1..r {
+            v = blake2b(&[&v], 64);
+            res.extend_from_slice(&v[..32]);
+        }
+        res.extend(blake2b(&[&v], len - 32 * r));
+        res
+    }
+
+    // H0 (RFC 9106 section 3.2)
+    fn initial_hash(&self, password: &[u8], salt: &[u8], len: usize) -> Vec<u8> {
+        let mut hasher = Blake2b::new();
+        for x in [
+            self.lanes,
+            len as u32,
+            self.m_cost,
+            self.t_cost,
+            Self::VERSION,
+            self.variant.id(),
+        ] {
+            hasher.update(&x.to_le_bytes());
+        }
+        for x in [password, salt, &self.secret, &self.ad] {
+            hasher.update(&(x.len() as u32).to_le_bytes());
+            hasher.update(x);
+        }
+        hasher.finalize().digest_u8()
+    }
+
+    // fBlaMka: BLAKE2b's addition with a multiplication of the low halves
+    #[inline(always)]
+    fn fbla(x: u64, y: u64) -> u64 {
+        x.wrapping_add(y)
+            .wrapping_add(2u64.wrapping_mul((x as u32 as u64) * (y as u32 as u64)))
+    }
+
+    #[inline(always)]
+    fn gb(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize) {
+        v[a] = Self::fbla(v[a], v[b]);
+        v[d] = (v[d] ^ v[a]).rotate_right(32);
+        v[c] = Self::fbla(v[c], v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(24);
+        v[a] = Self::fbla(v[a], v[b]);
+        v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = Self::fbla(v[c], v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(63);
+    }
+
+    // the permutation P, a BLAKE2b round without message words
+    fn permute(v: &mut [u64; 16]) {
+        Self::gb(v, 0, 4, 8, 12);
+        Self::gb(v, 1, 5, 9, 13);
+        Self::gb(v, 2, 6, 10, 14);
+        Self::gb(v, 3, 7, 11, 15);
+        Self::gb(v, 0, 5, 10, 15);
+        Self::gb(v, 1, 6, 11, 12);
+        Self::gb(v, 2, 7, 8, 13);
+        Self::gb(v, 3, 4, 9, 14);
+    }
+
+    // compression function G (RFC 9106 section 3.5)
+    fn compress(x: &Block, y: &Block) -> Block {
+        let r: Block = std::array::from_fn(|i| x[i] ^ y[i]);
+
+        // P on the rows (16 consecutive words), then on the columns
+        let mut q = r;
+        for row in q.chunks_exact_mut(16) {
+            let mut v: [u64; 16] = row.try_into().unwrap();
+            Self::permute(&mut v);
+            row.copy_from_slice(&v);
+        }
+        for i in 0..8 {
+            let mut v = [0; 16];
+            for j in 0..8 {
+                v[2 * j] = q[2 * i + 16 * j];
+                v[2 * j + 1] = q[2 * i + 16 * j + 1];
+            }
+            Self::permute(&mut v);
+            for j in 0..8 {
+                q[2 * i + 16 * j] = v[2 * j];
+                q[2 * i + 16 * j + 1] = v[2 * j + 1];
+            }
+        }
+
+        for (x, y) in q.iter_mut().zip(r) {
+            *x ^= y;
+        }
+        q
+    }
+
+    fn to_block(bytes: &[u8]) -> Block {
+        std::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * (i + 1)].try_into().unwrap()))
+    }
+
+    // The memory is kept as `lanes * SYNC_POINTS` segments. While a slice is being filled,
+    // each lane owns its segment of that slice and only reads the other segments.
+    fn fill_segment(
+        &self,
+        memory: &[Vec<Block>],
+        segment: &mut [Block],
+        pos: Position,
+        segment_len: usize,
+    ) {
+        let lane_len = segment_len * Self::SYNC_POINTS;
+        let block = |segment: &[Block], lane: usize, i: usize| -> Block {
+            if lane == pos.lane && i / segment_len == pos.slice {
+                segment[i % segment_len]
+            } else {
+                memory[lane * Self::SYNC_POINTS + i / segment_len][i % segment_len]
+            }
+        };
+
+        let data_independent = match self.variant {
+            Variant::Argon2i => true,
+            Variant::Argon2id => pos.pass == 0 && pos.slice < Self::SYNC_POINTS / 2,
+            Variant::Argon2d => false,
+        };
+        let mut input: Block = [0; 128];
+        let mut addresses: Block = [0; 128];
+        if data_independent {
+            input[..6].copy_from_slice(&[
+                pos.pass as u64,
+                pos.lane as u64,
+                pos.slice as u64,
+                (lane_len * self.lanes as usize) as u64,
+                self.t_cost as u64,
+                self.variant.id() as u64,
+            ]);
+        }
+        let next_addresses = |input: &mut Block, addresses: &mut Block| {
+            input[6] += 1;
+            *addresses = Self::compress(&[0; 128], &Self::compress(&[0; 128], input));
+        };
+
+        // the first two blocks of each lane are computed from H0
+        let start = if pos.pass == 0 && pos.slice == 0 {
+            if data_independent {
+                next_addresses(&mut input, &mut addresses);
+            }
+            2
+        } else {
+            0
+        };
+
+        for c in start..segment_len {
+            let i = pos.slice * segment_len + c;
+            let prev = block(segment, pos.lane, (i + lane_len - 1) % lane_len);
+
+            let rand = if data_independent {
+                if c % Self::ADDRESSES_IN_BLOCK == 0 {
+                    next_addresses(&mut input, &mut addresses);
+                }
+                addresses[c % Self::ADDRESSES_IN_BLOCK]
+            } else {
+                prev[0]
+            };
+
+            // reference block (RFC 9106 section 3.4.1.2): a lane, then a block among those
+            // already finished, chosen with a bias towards the recent ones
+            let ref_lane = if pos.pass == 0 && pos.slice == 0 {
+                pos.lane
+            } else {
+                ((rand >> 32) % self.lanes as u64) as usize
+            };
+            let finished = if pos.pass == 0 {
+                pos.slice * segment_len
+            } else {
+                lane_len - segment_len
+            };
+            let area = if ref_lane == pos.lane {
+                finished + c - 1
+            } else if c == 0 {
+                finished - 1
+            } else {
+                finished
+            };
+            let x = (rand & 0xffffffff).pow(2) >> 32;
+            let y = (area as u64 * x) >> 32;
+            let rel = area - 1 - y as usize;
+            let start = if pos.pass == 0 || pos.slice == Self::SYNC_POINTS - 1 {
+                0
+            } else {
+                (pos.slice + 1) * segment_len
+            };
+            let ref_index = (start + rel) % lane_len;
+
+            let new = Self::compress(&prev, &block(segment, ref_lane, ref_index));
+            if pos.pass == 0 {
+                segment[c] = new;
+            } else {
+                // version 0x13 xors the new block into the old one
+                for (x, y) in segment[c].iter_mut().zip(new) {
+                    *x ^= y;
+                }
+            }
+        }
+    }
+
+    // raw tag of `len` bytes
+    pub fn hash(&self, password: &[u8], salt: &[u8], len: usize) -> Result<Vec<u8>> {
+        if salt.len() < Self::MIN_SALT_SIZE {
+            return Err(HashError::ValueError(format!(
+                "salt must be at least {} bytes",
+                Self::MIN_SALT_SIZE
+            )));
+        }
+        if len < Self::MIN_OUTPUT_SIZE {
+            return Err(HashError::ValueError(format!(
+                "output must be at least {} bytes",
+                Self::MIN_OUTPUT_SIZE
+            )));
+        }
+
+        // the memory is rounded down to a multiple of 4 * lanes blocks
+        let lanes = self.lanes as usize;
+        let segment_len = self.m_cost as usize / (lanes * Self::SYNC_POINTS);
+        let h0 = self.initial_hash(password, salt, len);
+
+        let mut memory = vec![vec![[0; 128]; segment_len]; lanes * Self::SYNC_POINTS];
+        for lane in 0..lanes {
+            for (i, x) in memory[lane * Self::SYNC_POINTS][..2].iter_mut().enumerate() {
+                let bytes = Self::hash_long(
+                    &[&h0, &(i as u32).to_le_bytes(), &(lane as u32).to_le_bytes()],
+                    Self::BLOCK_SIZE,
+                );
+                *x = Self::to_block(&bytes);
+            }
+        }
+
+        let lanes_per_thread = lanes.div_ceil(self.threads);
+        for pass in 0..self.t_cost as usize {
+            for slice in 0..Self::SYNC_POINTS {
+                let mut segments: Vec<Vec<Block>> = (0..lanes)
+                    .map(|lane| std::mem::take(&mut memory[lane * Self::SYNC_POINTS + slice]))
+                    .collect();
+
+                let fill = |lane: usize, segment: &mut Vec<Block>| {
+                    let pos = Position { pass, slice, lane };
+                    self.fill_segment(&memory, segment, pos, segment_len);
+                };
+                if self.threads > 1 {
+                    thread::scope(|s| {
+                        for (i, chunk) in segments.chunks_mut(lanes_per_thread).enumerate() {
+                            s.spawn(move || {
+                                for (j, segment) in chunk.iter_mut().enumerate() {
+                                    fill(i * lanes_per_thread + j, segment);
+                                }
+                            });
+                        }
+                    });
+                } else {
+                    for (lane, segment) in segments.iter_mut().enumerate() {
+                        fill(lane, segment);
+                    }
+                }
+
+                for (lane, segment) in segments.into_iter().enumerate() {
+                    memory[lane * Self::SYNC_POINTS + slice] = segment;
+                }
+            }
+        }
+
+        // xor of the last block of each lane
+        let mut c = [0u64; 128];
+        for lane in 0..lanes {
+            let last = memory[(lane + 1) * Self::SYNC_POINTS - 1][segment_len - 1];
+            for (x, y) in c.iter_mut().zip(last) {
+                *x ^= y;
+            }
+        }
+        let c: Vec<u8> = c.iter().flat_map(|x| x.to_le_bytes()).collect();
+
+        Ok(Self::hash_long(&[&c], len))
+    }
+
+    // PHC string: $argon2id$v=19$m=<m_cost>,t=<t_cost>,p=<lanes>$<salt>$<tag>
+    pub fn hash_encoded(&self, password: &[u8], salt: &[u8], len: usize) -> Result<String> {
+        let tag = self.hash(password, salt, len)?;
+
+        Ok(format!(
+            "${}$v={}$m={},t={},p={}${}${}",
+            self.variant.name(),
+            Self::VERSION,
+            self.m_cost,
+            self.t_cost,
+            self.lanes,
+            to_b64(salt),
+            to_b64(&tag)
+        ))
+    }
+
+    // parses a PHC string into the parameters, the salt and the tag
+    pub fn decode(encoded: &str) -> Result<(Self, Vec<u8>, Vec<u8>)> {
+        let err = || HashError::ValueError(format!("invalid Argon2 PHC string: {}", encoded));
+
+        let fields: Vec<&str> = encoded.split('$').collect();
+        let ["", variant, version, params, salt, tag] = fields[..] else {
+            return Err(err());
+        };
+        let variant = Variant::from_name(variant).ok_or_else(err)?;
+        if version != format!("v={}", Self::VERSION) {
+            return Err(HashError::ValueError(format!(
+                "unsupported Argon2 version: {}",
+                version
+            )));
+        }
+
+        let mut costs = [0u32; 3];
+        let params: Vec<&str> = params.split(',').collect();
+        if params.len() != costs.len() {
+            return Err(err());
+        }
+        for ((x, p), key) in costs.iter_mut().zip(params).zip(["m=", "t=", "p="]) {
+            *x = p
+                .strip_prefix(key)
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(err)?;
+        }
+
+        let salt = from_b64(salt).ok_or_else(err)?;
+        let tag = from_b64(tag).ok_or_else(err)?;
+        let argon2 = Self::new(variant, costs[0], costs[1], costs[2])?;
+
+        Ok((argon2, salt, tag))
+    }
+
+    pub fn verify(encoded: &str, password: &[u8]) -> Result<bool> {
+        let (argon2, salt, tag) = Self::decode(encoded)?;
+
+        Ok(ct_eq(&argon2.hash(password, &salt, tag.len())?, &tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::to_hex;
+
+    use super::{Argon2, Variant};
+
+    fn rfc9106(variant: Variant, threads: usize) -> String {
+        let mut argon2 = Argon2::new(variant, 32, 3, 4).unwrap();
+        argon2.set_secret(&[3; 8]);
+        argon2.set_associated_data(&[4; 12]);
+        argon2.set_threads(threads);
+
+        to_hex(&argon2.hash(&[1; 32], &[2; 16], 32).unwrap())
+    }
+
+    #[test]
+    fn argon2() {
+        // RFC 9106 section 5
+        let tags = [
+            (
+                Variant::Argon2d,
+                "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb",
+            ),
+            (
+                Variant::Argon2i,
+                "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8",
+            ),
+            (
+                Variant::Argon2id,
+                "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659",
+            ),
+        ];
+        for (variant, tag) in tags {
+            for threads in [1, 2, 4] {
+                assert_eq!(rfc9106(variant, threads), tag, "{:?}", variant);
+            }
+        }
+    }
+
+    #[test]
+    fn argon2_memory() {
+        // several address blocks per segment, and an output longer than 64 bytes
+        let argon2 = Argon2::new(Variant::Argon2i, 2048, 2, 1).unwrap();
+        assert_eq!(
+            to_hex(&argon2.hash(b"password", b"somesaltsomesalt", 80).unwrap()),
+            "1fe0c81074cd75056ed7290d7fb9b81e3133f3948eba40148523da57e0303c3547e1502476478e9db80b16c8b1bfeb524554d4fd83a3375ab3c503163b2961c7d5a7f0ef1f1345f28bdc0c01151142fb"
+        );
+
+        let argon2 = Argon2::new(Variant::Argon2id, 1024, 2, 3).unwrap();
+        assert_eq!(
+            to_hex(&argon2.hash(b"password", b"somesalt", 16).unwrap()),
+            "4de3befd833b111dd85940960f762fb7"
+        );
+
+        // 35 KiB with 2 lanes uses 32 blocks
+        let argon2 = Argon2::new(Variant::Argon2id, 35, 1, 2).unwrap();
+        assert_eq!(
+            to_hex(&argon2.hash(b"pw", b"somesalt", 100).unwrap()),
+            "dcc4fbf2ec2aad77017e6019b63310b45cac3290e3184560856b53b67606581345bf407e9c45202b64c76877a6c0d9f5d5264477991108bb5a314c51d87863f219c37d3a85b8ba0ae158495ef3847aa1602e86a161aacee06274d0094da2eb22e3a4da4b"
+        );
+    }
+
+    #[test]
+    fn argon2_phc() {
+        let argon2 = Argon2::new(Variant::Argon2id, 64, 2, 1).unwrap();
+        let encoded = argon2.hash_encoded(b"password", b"somesalt", 32).unwrap();
+        assert_eq!(
+            encoded,
+            "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHQ$FqGkmHNGCd0BRW2kBt6fPZ2pPmyGwwChL8FGUhTOSSI"
+        );
+
+        assert!(Argon2::verify(&encoded, b"password").unwrap());
+        assert!(!Argon2::verify(&encoded, b"passwore").unwrap());
+
+        for invalid in [
+            "$argon2id$v=19$m=64,t=2$c29tZXNhbHQ$AAAAAA",
+            "$argon2id$v=16$m=64,t=2,p=1$c29tZXNhbHQ$AAAAAA",
+            "$argon2x$v=19$m=64,t=2,p=1$c29tZXNhbHQ$AAAAAA",
+            "$argon2id$v=19$m=4,t=2,p=1$c29tZXNhbHQ$AAAAAA",
+            "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHQ$AAAAAA$",
+            "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHQ$AA=A",
+        ] {
+            assert!(Argon2::verify(invalid, b"password").is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn argon2_params() {
+        assert!(Argon2::new(Variant::Argon2id, 32, 0, 4).is_err());
+        assert!(Argon2::new(Variant::Argon2id, 31, 1, 4).is_err());
+        assert!(Argon2::new(Variant::Argon2id, 32, 1, 0).is_err());
+
+        let argon2 = Argon2::new(Variant::Argon2id, 32, 1, 4).unwrap();
+        assert!(argon2.hash(b"password", b"short", 32).is_err());
+        assert!(argon2.hash(b"password", b"somesalt", 3).is_err());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod collision;
+pub mod length_extension;
@@ -0,0 +1,423 @@
+// Generic collision search on a hash truncated to n bits.
+//
+// The n-bit values are mapped back to messages (prefix || value), which makes the truncated
+// hash a function f from n-bit values to themselves. Iterating f from any start eventually
+// enters a cycle, and the two distinct predecessors of the cycle entry collide. Floyd's and
+// Brent's methods find the cycle in constant memory. The distinguished point method of van
+// Oorschot and Wiener runs many walks in parallel and only stores the points whose low bits
+// are zero, so that two walks meeting are noticed at the next such point.
+
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{digest::HashAlgorithm, error::HashError, util::available_threads};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Work {
+    // hash evaluations actually made
+    pub evaluations: u64,
+    // sqrt(pi / 2 * 2^n): the expected number of evaluations before a first collision
+    pub expected: f64,
+}
+
+impl Work {
+    pub fn ratio(&self) -> f64 {
+        self.evaluations as f64 / self.expected
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+    pub m1: Vec<u8>,
+    pub m2: Vec<u8>,
+    // the common first n bits of the digests, the remaining bits of the last byte zeroed
+    pub digest: Vec<u8>,
+    pub work: Work,
+}
+
+// SplitMix64, to spread the starting points
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+pub struct CollisionSearch<H: HashAlgorithm> {
+    bits: u32,
+    prefix: Vec<u8>,
+    threads: usize,
+    distinguished_bits: u32,
+    max_evaluations: u64,
+    _hash: PhantomData<H>,
+}
+
+// f with a count of its evaluations
+struct Walk<'a, H: HashAlgorithm> {
+    search: &'a CollisionSearch<H>,
+    evaluations: u64,
+}
+
+impl<H: HashAlgorithm> Walk<'_, H> {
+    fn f(&mut self, x: u64) -> u64 {
+        self.evaluations += 1;
+        self.search.truncated(&self.search.message(x))
+    }
+
+    fn exhausted(&self) -> bool {
+        self.evaluations >= self.search.max_evaluations
+    }
+
+    // Given walks from `a` and `b` that reach the same point after the same number of steps,
+    // returns the two distinct points mapped to where they merge. None if `a == b`, which
+    // happens when one start lies on the cycle (or the other walk).
+    fn locate(&mut self, mut a: u64, mut b: u64) -> Option<(u64, u64)> {
+        if a == b {
+            return None;
+        }
+        loop {
+            let (fa, fb) = (self.f(a), self.f(b));
+            if fa == fb {
+                return Some((a, b));
+            }
+            (a, b) = (fa, fb);
+        }
+    }
+}
+
+impl<H: HashAlgorithm> CollisionSearch<H> {
+    // Below this the map on n-bit values is too likely to be a permutation (or close to
+    // one), which has no collision to find.
+    pub const MIN_BITS: u32 = 8;
+    pub const MAX_BITS: u32 = 64;
+    // default budget, in multiples of the expected work
+    const MAX_WORK_RATIO: f64 = 64.0;
+
+    // collisions on the first `bits` bits of H(prefix || x)
+    pub fn new(bits: u32, prefix: &[u8]) -> Result<Self> {
+        let max = Self::MAX_BITS.min(8 * H::OUTPUT_SIZE as u32);
+        if bits < Self::MIN_BITS || bits > max {
+            return Err(HashError::ValueError(format!(
+                "number of bits must be in {}..={}",
+                Self::MIN_BITS,
+                max
+            )));
+        }
+
+        let mut res = Self {
+            bits,
+            prefix: prefix.to_vec(),
+            threads: available_threads(),
+            distinguished_bits: bits / 4,
+            max_evaluations: 0,
+            _hash: PhantomData,
+        };
+        // saturates to u64::MAX for large `bits`
+        res.max_evaluations = (Self::MAX_WORK_RATIO * res.expected_work()) as u64;
+        Ok(res)
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    // A point is distinguished when its low `bits` bits are zero. Walks are about 2^bits
+    // steps long, so more bits mean less memory but more work after the collision. Walks
+    // longer than the expected work are pointless, so at most half of the bits are used.
+    pub fn set_distinguished_bits(&mut self, bits: u32) {
+        self.distinguished_bits = bits.min(self.bits / 2);
+    }
+
+    // The searches give up with an error after about this many hash evaluations
+    pub fn set_max_evaluations(&mut self, evaluations: u64) {
+        self.max_evaluations = evaluations;
+    }
+
+    pub fn expected_work(&self) -> f64 {
+        (std::f64::consts::FRAC_PI_2 * 2f64.powi(self.bits as i32)).sqrt()
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.bits)
+    }
+
+    fn message(&self, x: u64) -> Vec<u8> {
+        let len = self.bits.div_ceil(8) as usize;
+        [&self.prefix, &x.to_be_bytes()[8 - len..]].concat()
+    }
+
+    fn truncated(&self, m: &[u8]) -> u64 {
+        let digest = H::digest(m);
+        let mut x = [0; 8];
+        let n = digest.as_ref().len().min(8);
+        x[..n].copy_from_slice(&digest.as_ref()[..n]);
+        u64::from_be_bytes(x) >> (64 - self.bits)
+    }
+
+    fn not_found(&self, evaluations: u64) -> HashError {
+        HashError::ValueError(format!(
+            "no collision found on {} bits after {} evaluations",
+            self.bits, evaluations
+        ))
+    }
+
+    fn collision(&self, (a, b): (u64, u64), evaluations: u64) -> Collision {
+        let x = self.truncated(&self.message(a)) << (64 - self.bits);
+
+        Collision {
+            m1: self.message(a),
+            m2: self.message(b),
+            digest: x.to_be_bytes()[..self.bits.div_ceil(8) as usize].to_vec(),
+            work: Work {
+                evaluations,
+                expected: self.expected_work(),
+            },
+        }
+    }
+
+    // Runs `find` from pseudorandom starts until it returns a pair. `find` gives up (returns
+    // None) once the walk is exhausted.
+    fn search_from_starts(
+        &self,
+        seed: u64,
+        find: impl Fn(&mut Walk<H>, u64) -> Option<(u64, u64)>,
+    ) -> Result<Collision> {
+        let mut walk = Walk {
+            search: self,
+            evaluations: 0,
+        };
+        for i in 0.. {
+            if walk.exhausted() {
+                break;
+            }
+            let start = splitmix64(seed.wrapping_add(i)) & self.mask();
+            if let Some(pair) = find(&mut walk, start) {
+                return Ok(self.collision(pair, walk.evaluations));
+            }
+        }
+        Err(self.not_found(walk.evaluations))
+    }
+
+    // Floyd's cycle finding: the tortoise moves one step and the hare two until they meet
+    pub fn floyd(&self, seed: u64) -> Result<Collision> {
+        self.search_from_starts(seed, |walk, x0| {
+            let mut t = walk.f(x0);
+            let mut h = walk.f(t);
+            while t != h {
+                if walk.exhausted() {
+                    return None;
+                }
+                t = walk.f(t);
+                h = walk.f(h);
+                h = walk.f(h);
+            }
+            // h is now a multiple of the cycle length ahead of x0
+            walk.locate(x0, h)
+        })
+    }
+
+    // Brent's cycle finding: the tortoise jumps to the hare at each power of two, which
+    // measures the cycle length with fewer evaluations than Floyd's method
+    pub fn brent(&self, seed: u64) -> Result<Collision> {
+        self.search_from_starts(seed, |walk, x0| {
+            let (mut power, mut lam) = (1u64, 1u64);
+            let mut t = x0;
+            let mut h = walk.f(x0);
+            while t != h {
+                if walk.exhausted() {
+                    return None;
+                }
+                if power == lam {
+                    t = h;
+                    power *= 2;
+                    lam = 0;
+                }
+                h = walk.f(h);
+                lam += 1;
+            }
+
+            let mut h = x0;
+            for _ in 0..lam {
+                h = walk.f(h);
+            }
+            walk.locate(x0, h)
+        })
+    }
+
+    // Parallel distinguished point search (van Oorschot and Wiener). Each thread walks from
+    // pseudorandom starts to a distinguished point and records the start and the length of
+    // the walk. Two walks ending at the same point merge somewhere, and the collision is
+    // found by replaying them.
+    pub fn distinguished_points(&self, seed: u64) -> Result<Collision>
+    where
+        H: Sync,
+    {
+        let dp_mask = (1u64 << self.distinguished_bits) - 1;
+        // walks caught in a cycle without distinguished points are abandoned. At most
+        // 20 * 2^32, as distinguished_bits <= 32.
+        let max_len = 20u64 << self.distinguished_bits;
+
+        let points: Mutex<HashMap<u64, (u64, u64)>> = Mutex::new(HashMap::new());
+        let found: Mutex<Option<(u64, u64)>> = Mutex::new(None);
+        let done = AtomicBool::new(false);
+        let evaluations = AtomicU64::new(0);
+
+        thread::scope(|s| {
+            for i in 0..self.threads {
+                let (points, found, done, evaluations) = (&points, &found, &done, &evaluations);
+                s.spawn(move || {
+                    let mut walk = Walk {
+                        search: self,
+                        evaluations: 0,
+                    };
+                    let (mut counter, mut counted) = (0u64, 0);
+                    while !done.load(Ordering::Relaxed) {
+                        // the evaluations since the last check go to the shared count
+                        let total = evaluations
+                            .fetch_add(walk.evaluations - counted, Ordering::Relaxed)
+                            + (walk.evaluations - counted);
+                        counted = walk.evaluations;
+                        if total >= self.max_evaluations {
+                            done.store(true, Ordering::Relaxed);
+                            break;
+                        }
+
+                        let start = splitmix64(
+                            seed.wrapping_add((counter * self.threads as u64) + i as u64),
+                        ) & self.mask();
+                        counter += 1;
+
+                        // at least one step, so that with no distinguished bits this is a
+                        // plain birthday search
+                        let mut x = walk.f(start);
+                        let mut len = 1;
+                        while x & dp_mask != 0 && len < max_len && !walk.exhausted() {
+                            x = walk.f(x);
+                            len += 1;
+                        }
+                        if x & dp_mask != 0 {
+                            continue;
+                        }
+
+                        let previous = points.lock().unwrap().insert(x, (start, len));
+                        let Some((start2, len2)) = previous else {
+                            continue;
+                        };
+                        // replay the longer walk until both are as far from the point
+                        let (mut a, mut b) = (start, start2);
+                        for _ in len2..len {
+                            a = walk.f(a);
+                        }
+                        for _ in len..len2 {
+                            b = walk.f(b);
+                        }
+                        if let Some(pair) = walk.locate(a, b) {
+                            found.lock().unwrap().get_or_insert(pair);
+                            done.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    evaluations.fetch_add(walk.evaluations - counted, Ordering::Relaxed);
+                });
+            }
+        });
+
+        let evaluations = evaluations.into_inner();
+        match found.into_inner().unwrap() {
+            Some(pair) => Ok(self.collision(pair, evaluations)),
+            None => Err(self.not_found(evaluations)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashAlgorithm, sha1::Sha1, sha256::Sha256, sha512::Sha512};
+
+    use super::{Collision, CollisionSearch};
+
+    fn check<H: HashAlgorithm>(collision: &Collision, bits: u32) {
+        assert_ne!(collision.m1, collision.m2);
+        let (d1, d2) = (H::digest(&collision.m1), H::digest(&collision.m2));
+        // the first `bits` bits of a digest
+        let n = bits.div_ceil(8) as usize;
+        let truncated = |d: &[u8]| {
+            let mut d = d[..n].to_vec();
+            d[n - 1] &= 0xff << (8 * n as u32 - bits);
+            d
+        };
+        assert_eq!(truncated(d1.as_ref()), truncated(d2.as_ref()));
+        assert_eq!(collision.digest, truncated(d1.as_ref()));
+        assert!(collision.work.evaluations > 0);
+    }
+
+    #[test]
+    fn collision_cycle() {
+        let search = CollisionSearch::<Sha256>::new(24, b"mitcrypto:").unwrap();
+        for seed in 0..4 {
+            let collision = search.floyd(seed).unwrap();
+            check::<Sha256>(&collision, 24);
+            assert!(collision.m1.starts_with(b"mitcrypto:"));
+            check::<Sha256>(&search.brent(seed).unwrap(), 24);
+        }
+
+        let search = CollisionSearch::<Sha1>::new(21, b"").unwrap();
+        let collision = search.brent(7).unwrap();
+        check::<Sha1>(&collision, 21);
+        assert_eq!(collision.m1.len(), 3);
+        assert!((collision.work.expected - 1814.99).abs() < 0.01);
+    }
+
+    #[test]
+    fn collision_distinguished_points() {
+        let mut search = CollisionSearch::<Sha512>::new(28, b"").unwrap();
+        for threads in [1, 4] {
+            search.set_threads(threads);
+            check::<Sha512>(&search.distinguished_points(1).unwrap(), 28);
+        }
+
+        search.set_distinguished_bits(0);
+        check::<Sha512>(&search.distinguished_points(2).unwrap(), 28);
+
+        assert!(CollisionSearch::<Sha256>::new(0, b"").is_err());
+        assert!(CollisionSearch::<Sha256>::new(65, b"").is_err());
+    }
+
+    #[test]
+    fn collision_small() {
+        // a permutation of {0, 1}, which has no collision
+        assert!(CollisionSearch::<Sha256>::new(1, b"a").is_err());
+        assert!(CollisionSearch::<Sha256>::new(7, b"").is_err());
+
+        for bits in 8..12 {
+            let mut search = CollisionSearch::<Sha256>::new(bits, b"a").unwrap();
+            search.set_threads(2);
+            for seed in 0..8 {
+                check::<Sha256>(&search.floyd(seed).unwrap(), bits);
+                check::<Sha256>(&search.brent(seed).unwrap(), bits);
+                check::<Sha256>(&search.distinguished_points(seed).unwrap(), bits);
+            }
+        }
+
+        let mut search = CollisionSearch::<Sha256>::new(64, b"").unwrap();
+        search.set_distinguished_bits(63);
+        assert_eq!(search.distinguished_bits, 32);
+        search.set_max_evaluations(1000);
+        assert!(search.distinguished_points(0).is_err());
+
+        // out of budget
+        let mut search = CollisionSearch::<Sha256>::new(40, b"").unwrap();
+        search.set_max_evaluations(1000);
+        assert!(search.floyd(0).is_err());
+        assert!(search.brent(0).is_err());
+        assert!(search.distinguished_points(0).is_err());
+    }
+}
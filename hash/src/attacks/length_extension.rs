@@ -0,0 +1,110 @@
+// Length-extension forgeries against Merkle-Damgard hashes used as H(secret || message).
+//
+// Knowing H(secret || message) and the length of the secret, the digest can be resumed as a
+// chaining value to compute H(secret || message || glue || suffix) without the secret, where
+// glue is the padding the original hash appended to secret || message.
+
+use crate::{digest::HashDigest, error::HashError, sha1::Sha1, sha256::Sha256, sha512::Sha512};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forgery {
+    // message || glue || suffix, to be sent in place of the original message
+    pub message: Vec<u8>,
+    // H(secret || message || glue || suffix)
+    pub digest: Vec<u8>,
+}
+
+fn words<const N: usize, const W: usize>(digest: &[u8]) -> Result<[[u8; W]; N]> {
+    if digest.len() != N * W {
+        return Err(HashError::ValueError(format!(
+            "digest must be {} bytes",
+            N * W
+        )));
+    }
+
+    let mut res = [[0; W]; N];
+    for (word, chunk) in res.iter_mut().zip(digest.chunks(W)) {
+        word.copy_from_slice(chunk);
+    }
+    Ok(res)
+}
+
+pub fn sha1(digest: &[u8], message: &[u8], secret_len: usize, suffix: &[u8]) -> Result<Forgery> {
+    let hs = words::<5, 4>(digest)?.map(u32::from_be_bytes);
+    let glue = Sha1::pad(vec![], (secret_len + message.len()) * 8);
+
+    let mut hasher = Sha1::from_state(hs, secret_len + message.len() + glue.len());
+    hasher.update(suffix);
+
+    Ok(Forgery {
+        message: [message, &glue, suffix].concat(),
+        digest: hasher.finalize().digest_u8(),
+    })
+}
+
+pub fn sha256(digest: &[u8], message: &[u8], secret_len: usize, suffix: &[u8]) -> Result<Forgery> {
+    let hs = words::<8, 4>(digest)?.map(u32::from_be_bytes);
+    let glue = Sha256::pad(vec![], (secret_len + message.len()) * 8);
+
+    let mut hasher = Sha256::from_state(hs, secret_len + message.len() + glue.len());
+    hasher.update(suffix);
+
+    Ok(Forgery {
+        message: [message, &glue, suffix].concat(),
+        digest: hasher.finalize().digest_u8(),
+    })
+}
+
+pub fn sha512(digest: &[u8], message: &[u8], secret_len: usize, suffix: &[u8]) -> Result<Forgery> {
+    let hs = words::<8, 8>(digest)?.map(u64::from_be_bytes);
+    let glue = Sha512::pad(vec![], (secret_len + message.len()) * 8);
+
+    let mut hasher = Sha512::from_state(hs, secret_len + message.len() + glue.len());
+    hasher.update(suffix);
+
+    Ok(Forgery {
+        message: [message, &glue, suffix].concat(),
+        digest: hasher.finalize().digest_u8(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashDigest, sha1::Sha1, sha256::Sha256, sha512::Sha512};
+
+    #[test]
+    fn length_extension() {
+        let message = b"user=guest&expires=1700000000".to_vec();
+        let suffix = b"&role=admin".to_vec();
+
+        for secret in [b"".to_vec(), b"k".to_vec(), vec![0x42; 34], vec![0x17; 200]] {
+            let secret_message = [secret.clone(), message.clone()].concat();
+
+            let md = Sha1::hash(secret_message.clone()).unwrap().digest_u8();
+            let forgery = super::sha1(&md, &message, secret.len(), &suffix).unwrap();
+            assert!(forgery.message.starts_with(&message));
+            assert!(forgery.message.ends_with(&suffix));
+            let md = Sha1::hash([secret.clone(), forgery.message].concat()).unwrap();
+            assert_eq!(md.digest_u8(), forgery.digest);
+
+            let md = Sha256::hash(secret_message.clone()).unwrap().digest_u8();
+            let forgery = super::sha256(&md, &message, secret.len(), &suffix).unwrap();
+            let md = Sha256::hash([secret.clone(), forgery.message].concat()).unwrap();
+            assert_eq!(md.digest_u8(), forgery.digest);
+
+            let md = Sha512::hash(secret_message).unwrap().digest_u8();
+            let forgery = super::sha512(&md, &message, secret.len(), &suffix).unwrap();
+            let md = Sha512::hash([secret.clone(), forgery.message].concat()).unwrap();
+            assert_eq!(md.digest_u8(), forgery.digest);
+        }
+    }
+
+    #[test]
+    fn length_extension_wrong_digest() {
+        assert!(super::sha1(&[0; 32], b"", 8, b"").is_err());
+        assert!(super::sha256(&[0; 20], b"", 8, b"").is_err());
+        assert!(super::sha512(&[0; 32], b"", 8, b"").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+// Hashing of many independent messages at once.
+//
+// Messages are sorted by length and hashed in groups, one message per lane of a
+// multi-buffer compression function (compiled for AVX2 when the CPU supports it).
+// Large batches are additionally split across threads. Results are returned in input order.
+
+use std::thread;
+
+use crate::{
+    accel::{self, Backend},
+    digest::HashDigest,
+    sha256::{Sha256, Sha256Digest},
+    sha512::{Sha512, Sha512Digest},
+    util::available_threads,
+};
+
+// batches with at least this many bytes in total are split across threads
+const PARALLEL_MIN_LEN: usize = 1 << 16;
+
+macro_rules! multi_buffer {
+    ($hasher:ident, $word:ty, $lanes:expr, $rounds:expr, $block:expr, $compress:ident, $compress_avx2:ident, $group:ident) => {
+        // the state is stored word-major, `hs[i][lane]`, so that each step is a vector operation
+        #[inline(always)]
+        fn $compress(hs: &mut [[$word; $lanes]; 8], blocks: &[&[u8]; $lanes]) {
+            const WORD_SIZE: usize = std::mem::size_of::<$word>();
+
+            let mut wts = [[0 as $word; $lanes]; $rounds];
+            for t in 0..16 {
+                for l in 0..$lanes {
+                    wts[t][l] = <$word>::from_be_bytes(
+                        blocks[l][WORD_SIZE * t..WORD_SIZE * (t + 1)]
+                            .try_into()
+                            .unwrap(),
+                    );
+                }
+            }
+            for t in 16..$rounds {
+                for l in 0..$lanes {
+                    wts[t][l] = $hasher::s1(wts[t - 2][l])
+                        .wrapping_add(wts[t - 7][l])
+                        .wrapping_add($hasher::s0(wts[t - 15][l]))
+                        .wrapping_add(wts[t - 16][l]);
+                }
+            }
+
+            let mut v = *hs;
+            for t in 0..$rounds {
+                let mut t1 = [0 as $word; $lanes];
+                let mut t2 = [0 as $word; $lanes];
+                for l in 0..$lanes {
+                    t1[l] = v[7][l]
+                        .wrapping_add($hasher::S1(v[4][l]))
+                        .wrapping_add($hasher::ch(v[4][l], v[5][l], v[6][l]))
+                        .wrapping_add($hasher::kt(t))
+                        .wrapping_add(wts[t][l]);
+                    t2[l] =
+                        $hasher::S0(v[0][l]).wrapping_add($hasher::maj(v[0][l], v[1][l], v[2][l]));
+                }
+
+                v = [
+                    std::array::from_fn(|l| t1[l].wrapping_add(t2[l])),
+                    v[0],
+                    v[1],
+                    v[2],
+                    std::array::from_fn(|l| v[3][l].wrapping_add(t1[l])),
+                    v[4],
+                    v[5],
+                    v[6],
+                ];
+            }
+
+            for i in 0..8 {
+                for l in 0..$lanes {
+                    hs[i][l] = hs[i][l].wrapping_add(v[i][l]);
+                }
+            }
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        #[target_feature(enable = "avx2")]
+        unsafe fn $compress_avx2(hs: &mut [[$word; $lanes]; 8], blocks: &[&[u8]; $lanes]) {
+            $compress(hs, blocks);
+        }
+
+        // hashes up to `$lanes` messages side by side
+        fn $group(messages: &[&[u8]]) -> Vec<[$word; 8]> {
+            // The full blocks are read from the messages in place. The rest of each message
+            // and its padding (0x80, zeros and the bit length in the last $block / 8 bytes)
+            // make one or two more blocks, built in a buffer shared by the group.
+            let mut tails = [[0u8; 2 * $block]; $lanes];
+            let mut ends = [0; $lanes];
+            for (l, m) in messages.iter().enumerate() {
+                let (full, rem) = (m.len() / $block, m.len() % $block);
+                let tail_len = if rem + 1 + $block / 8 <= $block {
+                    $block
+                } else {
+                    2 * $block
+                };
+                let tail = &mut tails[l][..tail_len];
+                tail[..rem].copy_from_slice(&m[$block * full..]);
+                tail[rem] = 0x80;
+                tail[tail_len - $block / 8..]
+                    .copy_from_slice(&(8 * m.len() as u128).to_be_bytes()[16 - $block / 8..]);
+                ends[l] = full + tail_len / $block;
+            }
+            let n = ends.iter().max().copied().unwrap_or(0);
+            let zero = [0; $block];
+            #[cfg(target_arch = "x86_64")]
+            let avx2 = std::arch::is_x86_feature_detected!("avx2");
+
+            let mut hs = $hasher::IV.map(|x| [x; $lanes]);
+            let mut res = vec![[0; 8]; messages.len()];
+            for b in 0..n {
+                // lanes without a block left (or without a message) compress zeros
+                let blocks: [&[u8]; $lanes] = std::array::from_fn(|l| match messages.get(l) {
+                    Some(m) if b < ends[l] => {
+                        let full = m.len() / $block;
+                        if b < full {
+                            &m[$block * b..$block * (b + 1)]
+                        } else {
+                            &tails[l][$block * (b - full)..$block * (b - full + 1)]
+                        }
+                    }
+                    _ => &zero,
+                });
+
+                #[cfg(target_arch = "x86_64")]
+                if avx2 {
+                    unsafe { $compress_avx2(&mut hs, &blocks) };
+                } else {
+                    $compress(&mut hs, &blocks);
+                }
+                #[cfg(not(target_arch = "x86_64"))]
+                $compress(&mut hs, &blocks);
+
+                for (l, end) in ends.iter().enumerate().take(messages.len()) {
+                    if *end == b + 1 {
+                        res[l] = hs.map(|w| w[l]);
+                    }
+                }
+            }
+            res
+        }
+    };
+}
+
+multi_buffer!(
+    Sha256,
+    u32,
+    8,
+    64,
+    64,
+    sha256_compress,
+    sha256_compress_avx2,
+    sha256_group
+);
+multi_buffer!(
+    Sha512,
+    u64,
+    4,
+    80,
+    128,
+    sha512_compress,
+    sha512_compress_avx2,
+    sha512_group
+);
+
+pub struct Batch {}
+
+impl Batch {
+    const SHA256_LANES: usize = 8;
+    const SHA512_LANES: usize = 4;
+
+    // sorts by length so that the lanes of a group finish together, hashes the groups
+    // (in parallel for large batches) and puts the results back in input order
+    fn run<M, W, F>(messages: &[M], lanes: usize, group: F) -> Vec<W>
+    where
+        M: AsRef<[u8]> + Sync,
+        W: Copy + Default + Send,
+        F: Fn(&[&[u8]]) -> Vec<W> + Sync,
+    {
+        let mut order: Vec<usize> = (0..messages.len()).collect();
+        order.sort_by_key(|&i| messages[i].as_ref().len());
+        let sorted: Vec<&[u8]> = order.iter().map(|&i| messages[i].as_ref()).collect();
+
+        let total: usize = sorted.iter().map(|x| x.len()).sum();
+        let threads = if total >= PARALLEL_MIN_LEN {
+            available_threads()
+        } else {
+            1
+        };
+        let groups: Vec<&[&[u8]]> = sorted.chunks(lanes).collect();
+        let per_thread = groups.len().div_ceil(threads).max(1);
+
+        let results: Vec<W> = if threads > 1 {
+            thread::scope(|s| {
+                let handles: Vec<_> = groups
+                    .chunks(per_thread)
+                    .map(|x| s.spawn(|| x.iter().flat_map(|g| group(g)).collect::<Vec<_>>()))
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|x| x.join().unwrap())
+                    .collect()
+            })
+        } else {
+            groups.iter().flat_map(|g| group(g)).collect()
+        };
+
+        let mut res = vec![W::default(); messages.len()];
+        for (i, x) in order.into_iter().zip(results) {
+            res[i] = x;
+        }
+        res
+    }
+
+    pub fn sha256<M: AsRef<[u8]> + Sync>(messages: &[M]) -> Vec<Sha256Digest> {
+        let res = if accel::backend() == Backend::ShaNi {
+            // SHA-NI on one message at a time beats the vector lanes
+            Self::run(messages, Self::SHA256_LANES, |g| {
+                g.iter()
+                    .map(|m| {
+                        let mut hasher = Sha256::new();
+                        hasher.update(m);
+                        hasher.finalize().digest()
+                    })
+                    .collect()
+            })
+        } else {
+            Self::run(messages, Self::SHA256_LANES, sha256_group)
+        };
+
+        res.into_iter().map(Sha256Digest::new).collect()
+    }
+
+    pub fn sha512<M: AsRef<[u8]> + Sync>(messages: &[M]) -> Vec<Sha512Digest> {
+        Self::run(messages, Self::SHA512_LANES, sha512_group)
+            .into_iter()
+            .map(Sha512Digest::new)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashDigest, sha256::Sha256, sha512::Sha512};
+
+    use super::{sha256_group, sha512_group, Batch};
+
+    // lengths around the padding and block boundaries, in no particular order
+    fn messages() -> Vec<Vec<u8>> {
+        [
+            0, 300, 55, 56, 1, 64, 111, 112, 128, 3, 1000, 63, 119, 120, 129, 65, 200, 0,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, len)| (0..len).map(|j| (i * 7 + j) as u8).collect())
+        .collect()
+    }
+
+    #[test]
+    fn batch_sha256() {
+        let ms = messages();
+        let mds = Batch::sha256(&ms);
+        assert_eq!(mds.len(), ms.len());
+        for (m, md) in ms.iter().zip(&mds) {
+            assert_eq!(md.hexdigest(), Sha256::hash(m.clone()).unwrap().hexdigest());
+        }
+
+        // the lanes regardless of the dispatched backend
+        let refs: Vec<&[u8]> = ms.iter().map(|x| x.as_slice()).collect();
+        for g in refs.chunks(8) {
+            for (m, md) in g.iter().zip(sha256_group(g)) {
+                assert_eq!(md, Sha256::hash(m.to_vec()).unwrap().digest());
+            }
+        }
+
+        assert!(Batch::sha256::<Vec<u8>>(&[]).is_empty());
+    }
+
+    #[test]
+    fn batch_sha512() {
+        let ms = messages();
+        let mds = Batch::sha512(&ms);
+        for (m, md) in ms.iter().zip(&mds) {
+            assert_eq!(md.hexdigest(), Sha512::hash(m.clone()).unwrap().hexdigest());
+        }
+
+        let refs: Vec<&[u8]> = ms.iter().map(|x| x.as_slice()).collect();
+        for g in refs.chunks(4) {
+            for (m, md) in g.iter().zip(sha512_group(g)) {
+                assert_eq!(md, Sha512::hash(m.to_vec()).unwrap().digest());
+            }
+        }
+    }
+
+    #[test]
+    fn batch_parallel() {
+        // large enough to be split across threads
+        let ms: Vec<Vec<u8>> = (0..600u32)
+            .map(|i| i.to_le_bytes().repeat(i as usize % 97))
+            .collect();
+
+        for (m, md) in ms.iter().zip(Batch::sha256(&ms)) {
+            assert_eq!(md.digest(), Sha256::hash(m.clone()).unwrap().digest());
+        }
+        for (m, md) in ms.iter().zip(Batch::sha512(&ms)) {
+            assert_eq!(md.digest(), Sha512::hash(m.clone()).unwrap().digest());
+        }
+    }
+
+    // cargo test --release batch_throughput -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn batch_throughput() {
+        use std::time::Instant;
+
+        let ms: Vec<Vec<u8>> = (0..4096u32).map(|i| i.to_le_bytes().repeat(64)).collect();
+        let refs: Vec<&[u8]> = ms.iter().map(|x| x.as_slice()).collect();
+        let mb = ms.iter().map(|x| x.len()).sum::<usize>() as f64 / 1e6;
+        let rate = |f: &dyn Fn()| {
+            let t = Instant::now();
+            f();
+            mb / t.elapsed().as_secs_f64()
+        };
+
+        let seq = rate(&|| {
+            for m in &ms {
+                std::hint::black_box(Sha256::hash(m.clone()).unwrap());
+            }
+        });
+        let lanes = rate(&|| {
+            for g in refs.chunks(8) {
+                std::hint::black_box(sha256_group(g));
+            }
+        });
+        println!(
+            "SHA-256: sequential {:.0} MB/s, 8 lanes {:.0} MB/s",
+            seq, lanes
+        );
+
+        let seq = rate(&|| {
+            for m in &ms {
+                std::hint::black_box(Sha512::hash(m.clone()).unwrap());
+            }
+        });
+        let lanes = rate(&|| {
+            for g in refs.chunks(4) {
+                std::hint::black_box(sha512_group(g));
+            }
+        });
+        println!(
+            "SHA-512: sequential {:.0} MB/s, 4 lanes {:.0} MB/s",
+            seq, lanes
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    util::ct_eq,
+};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// message word permutations, shared by BLAKE2b and BLAKE2s
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+macro_rules! blake2 {
+    ($name:ident, $digest:ident, $word:ty, $block:expr, $out:expr, $rounds:expr, $rot:expr, $iv:expr) => {
+        pub struct $digest {
+            data: <$digest as HashDigest>::Digest,
+        }
+
+        impl $digest {
+            fn new(data: <$digest as HashDigest>::Digest) -> Self {
+                Self { data }
+            }
+        }
+
+        impl HashDigest for $digest {
+            type Digest = Vec<u8>;
+            type Bytes = Vec<u8>;
+
+            fn digest(&self) -> Self::Digest {
+                self.data.clone()
+            }
+
+            fn digest_u8(&self) -> Vec<u8> {
+                self.data.clone()
+            }
+
+            fn into_bytes(self) -> Self::Bytes {
+                self.data
+            }
+
+            fn hexdigest(&self) -> String {
+                let mut res = String::new();
+                for i in &self.data {
+                    res.push_str(&format!("{:0>2x?}", i));
+                }
+                res
+            }
+        }
+
+        #[derive(Clone)]
+        pub struct $name {
+            hs: [$word; 8],
+            // number of message bytes compressed so far
+            t: u128,
+            buffer: Vec<u8>,
+            out_len: usize,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl $name {
+            // bytes
+            const BLOCK_SIZE: usize = $block;
+            const WORD_SIZE: usize = std::mem::size_of::<$word>();
+            pub const DIGEST_SIZE: usize = $out;
+            pub const KEY_SIZE: usize = $out;
+            pub const SALT_SIZE: usize = $out / 4;
+            pub const PERSONAL_SIZE: usize = $out / 4;
+            const ROUNDS: usize = $rounds;
+            const ROT: [u32; 4] = $rot;
+            const IV: [$word; 8] = $iv;
+
+            #[inline(always)]
+            fn g(v: &mut [$word; 16], a: usize, b: usize, c: usize, d: usize, x: $word, y: $word) {
+                v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+                v[d] = (v[d] ^ v[a]).rotate_right(Self::ROT[0]);
+                v[c] = v[c].wrapping_add(v[d]);
+                v[b] = (v[b] ^ v[c]).rotate_right(Self::ROT[1]);
+                v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+                v[d] = (v[d] ^ v[a]).rotate_right(Self::ROT[2]);
+                v[c] = v[c].wrapping_add(v[d]);
+                v[b] = (v[b] ^ v[c]).rotate_right(Self::ROT[3]);
+            }
+
+            fn compress(hs: &mut [$word; 8], block: &[u8; Self::BLOCK_SIZE], t: u128, last: bool) {
+                let mut m = [0 as $word; 16];
+                for (w, x) in m.iter_mut().zip(block.chunks_exact(Self::WORD_SIZE)) {
+                    *w = <$word>::from_le_bytes(x.try_into().unwrap());
+                }
+
+                let mut v = [0 as $word; 16];
+                v[..8].copy_from_slice(hs);
+                v[8..].copy_from_slice(&Self::IV);
+                v[12] ^= t as $word;
+                v[13] ^= (t >> (8 * Self::WORD_SIZE)) as $word;
+                if last {
+                    v[14] = !v[14];
+                }
+
+                for r in 0..Self::ROUNDS {
+                    let s = &SIGMA[r % 10];
+                    Self::g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+                    Self::g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+                    Self::g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+                    Self::g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+                    Self::g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+                    Self::g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+                    Self::g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+                    Self::g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+                }
+
+                for i in 0..8 {
+                    hs[i] ^= v[i] ^ v[i + 8];
+                }
+            }
+
+            pub fn new() -> Self {
+                Self::with_params(Self::DIGEST_SIZE, &[], &[], &[]).unwrap()
+            }
+
+            // keyed (MAC) mode
+            pub fn new_keyed(key: &[u8], out_len: usize) -> Result<Self> {
+                Self::with_params(out_len, key, &[], &[])
+            }
+
+            // `salt` and `personal` shorter than SALT_SIZE / PERSONAL_SIZE are padded with zeros
+            pub fn with_params(
+                out_len: usize,
+                key: &[u8],
+                salt: &[u8],
+                personal: &[u8],
+            ) -> Result<Self> {
+                if out_len == 0 || out_len > Self::DIGEST_SIZE {
+                    return Err(HashError::ValueError(format!(
+                        "digest length must be in 1..={}",
+                        Self::DIGEST_SIZE
+                    )));
+                }
+                if key.len() > Self::KEY_SIZE {
+                    return Err(HashError::ValueError(format!(
+                        "key must be at most {} bytes",
+                        Self::KEY_SIZE
+                    )));
+                }
+                if salt.len() > Self::SALT_SIZE {
+                    return Err(HashError::ValueError(format!(
+                        "salt must be at most {} bytes",
+                        Self::SALT_SIZE
+                    )));
+                }
+                if personal.len() > Self::PERSONAL_SIZE {
+                    return Err(HashError::ValueError(format!(
+                        "personalization must be at most {} bytes",
+                        Self::PERSONAL_SIZE
+                    )));
+                }
+
+                // parameter block: digest length, key length, fanout = 1, depth = 1,
+                // sequential mode (all tree parameters zero), salt, personalization
+                let mut hs = Self::IV;
+                hs[0] ^= (0x01010000 | (key.len() << 8) | out_len) as $word;
+                let mut words = salt.to_vec();
+                words.resize(Self::SALT_SIZE, 0);
+                words.extend_from_slice(personal);
+                words.resize(Self::SALT_SIZE + Self::PERSONAL_SIZE, 0);
+                for (h, x) in hs[4..].iter_mut().zip(words.chunks_exact(Self::WORD_SIZE)) {
+                    *h ^= <$word>::from_le_bytes(x.try_into().unwrap());
+                }
+
+                // the key is processed as a full first block
+                let mut buffer = key.to_vec();
+                if !key.is_empty() {
+                    buffer.resize(Self::BLOCK_SIZE, 0);
+                }
+
+                Ok(Self {
+                    hs,
+                    t: 0,
+                    buffer,
+                    out_len,
+                })
+            }
+
+            pub fn output_size(&self) -> usize {
+                self.out_len
+            }
+
+            pub fn update(&mut self, m: &[u8]) {
+                let mut m = m;
+
+                // the final block is compressed with a flag, so a full block is kept
+                // buffered until more input arrives
+                while !m.is_empty() {
+                    if self.buffer.len() == Self::BLOCK_SIZE {
+                        self.t += Self::BLOCK_SIZE as u128;
+                        Self::compress(
+                            &mut self.hs,
+                            self.buffer[..].try_into().unwrap(),
+                            self.t,
+                            false,
+                        );
+                        self.buffer.clear();
+                    }
+
+                    let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+                    self.buffer.extend_from_slice(&m[..n]);
+                    m = &m[n..];
+                }
+            }
+
+            pub fn finalize(self) -> $digest {
+                let mut hs = self.hs;
+                let t = self.t + self.buffer.len() as u128;
+                let mut block = self.buffer;
+                block.resize(Self::BLOCK_SIZE, 0);
+                Self::compress(&mut hs, block[..].try_into().unwrap(), t, true);
+
+                let mut data: Vec<u8> = hs.iter().flat_map(|x| x.to_le_bytes()).collect();
+                data.truncate(self.out_len);
+
+                $digest::new(data)
+            }
+
+            pub fn verify(self, tag: &[u8]) -> bool {
+                ct_eq(&self.finalize().digest_u8(), tag)
+            }
+
+            pub fn hash(m: Vec<u8>) -> std::result::Result<$digest, Vec<u8>> {
+                let mut hasher = Self::new();
+                hasher.update(&m);
+
+                Ok(hasher.finalize())
+            }
+
+            pub fn mac(key: &[u8], m: &[u8], out_len: usize) -> Result<Vec<u8>> {
+                let mut hasher = Self::new_keyed(key, out_len)?;
+                hasher.update(m);
+
+                Ok(hasher.finalize().digest_u8())
+            }
+        }
+
+        impl HashAlgorithm for $name {
+            type Output = $digest;
+            const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+            const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+            fn new() -> Self {
+                $name::new()
+            }
+
+            fn update(&mut self, m: &[u8]) {
+                $name::update(self, m);
+            }
+
+            fn finalize(self) -> Self::Output {
+                $name::finalize(self)
+            }
+        }
+    };
+}
+
+// BLAKE2 (RFC 7693)
+blake2!(
+    Blake2b,
+    Blake2bDigest,
+    u64,
+    128,
+    64,
+    12,
+    [32, 24, 16, 63],
+    [
+        0x6a09e667f3bcc908,
+        0xbb67ae8584caa73b,
+        0x3c6ef372fe94f82b,
+        0xa54ff53a5f1d36f1,
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ]
+);
+blake2!(
+    Blake2s,
+    Blake2sDigest,
+    u32,
+    64,
+    32,
+    10,
+    [16, 12, 8, 7],
+    [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashDigest, hmac::Hmac, util::to_hex};
+
+    use super::{Blake2b, Blake2s};
+
+    #[test]
+    fn blake2b() {
+        // RFC 7693 appendix A
+        assert_eq!(
+            Blake2b::hash(b"abc".to_vec()).unwrap().hexdigest(),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            Blake2b::hash(vec![]).unwrap().hexdigest(),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        // exactly one block
+        assert_eq!(
+            Blake2b::hash(vec![0; 128]).unwrap().hexdigest(),
+            "865939e120e6805438478841afb739ae4250cf372653078a065cdcfffca4caf798e6d462b65d658fc165782640eded70963449ae1500fb0f24981d7727e22c41"
+        );
+    }
+
+    #[test]
+    fn blake2s() {
+        // RFC 7693 appendix B
+        assert_eq!(
+            Blake2s::hash(b"abc".to_vec()).unwrap().hexdigest(),
+            "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"
+        );
+        assert_eq!(
+            Blake2s::hash(vec![]).unwrap().hexdigest(),
+            "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9"
+        );
+    }
+
+    #[test]
+    fn blake2_keyed() {
+        // last entries of the reference blake2b-kat.txt / blake2s-kat.txt
+        let m: Vec<u8> = (0..255).collect();
+        let key: Vec<u8> = (0..64).collect();
+        let tag = Blake2b::mac(&key, &m, 64).unwrap();
+        assert_eq!(
+            to_hex(&tag),
+            "142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e92484be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461"
+        );
+        assert_eq!(
+            to_hex(&Blake2s::mac(&key[..32], &m, 32).unwrap()),
+            "3fb735061abc519dfe979e54c1ee5bfad0a9d858b3315bad34bde999efd724dd"
+        );
+
+        let mut hasher = Blake2b::new_keyed(&key, 64).unwrap();
+        hasher.update(&m);
+        assert!(hasher.clone().verify(&tag));
+        assert!(!hasher.verify(&tag[..32]));
+
+        assert!(Blake2b::new_keyed(&[0; 65], 64).is_err());
+        assert!(Blake2s::new_keyed(&[0; 32], 33).is_err());
+    }
+
+    #[test]
+    fn blake2_params() {
+        let mut hasher = Blake2b::with_params(20, &[], b"saltysalt", b"mitcrypto").unwrap();
+        hasher.update(b"hello world");
+        assert_eq!(hasher.output_size(), 20);
+        assert_eq!(
+            hasher.finalize().hexdigest(),
+            "357fc3a66ef3cc2fef5114d6c779cda350feaf96"
+        );
+
+        let mut hasher = Blake2s::with_params(16, b"secret", b"salt", b"person").unwrap();
+        hasher.update(b"hello world");
+        assert_eq!(
+            hasher.finalize().hexdigest(),
+            "864314231649d5d6aca55bcd1375c621"
+        );
+
+        assert!(Blake2s::with_params(32, &[], &[0; 9], &[]).is_err());
+        assert!(Blake2b::with_params(0, &[], &[], &[]).is_err());
+    }
+
+    #[test]
+    fn blake2_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        for chunk_size in [1, 7, 127, 128, 129, 300] {
+            let mut hasher = Blake2b::with_params(32, &[], &[], &[]).unwrap();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finalize().hexdigest(),
+                "b372d0608f720c8c3dd41e9c8eecb10143b41abe520b616607e754bf79c08331"
+            );
+        }
+    }
+
+    #[test]
+    fn blake2_hmac() {
+        // the HashAlgorithm impl lets BLAKE2 drive the generic constructions
+        let tag = Hmac::<Blake2s>::mac(b"key", b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(
+            to_hex(&tag),
+            "f93215bb90d4af4c3061cd932fb169fb8bb8a91d0b4022baea1271e1323cd9a0"
+        );
+    }
+}
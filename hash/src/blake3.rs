@@ -0,0 +1,708 @@
+use std::thread;
+
+use crate::{
+    digest::{ExtendableOutput, HashAlgorithm, HashDigest, XofReader},
+    util::available_threads,
+};
+
+pub struct Blake3Digest {
+    data: <Blake3Digest as HashDigest>::Digest,
+}
+
+impl Blake3Digest {
+    fn new(data: <Blake3Digest as HashDigest>::Digest) -> Self {
+        Self { data }
+    }
+}
+
+impl HashDigest for Blake3Digest {
+    type Digest = [u8; 32];
+    type Bytes = [u8; 32];
+
+    fn digest(&self) -> Self::Digest {
+        self.data
+    }
+
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.to_vec()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        self.data
+    }
+
+    fn hexdigest(&self) -> String {
+        let mut res = String::new();
+        for i in self.data {
+            res.push_str(&format!("{:0>2x?}", i));
+        }
+        res
+    }
+}
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+// domain separation flags
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+// bytes
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+// inputs at least this long are split across threads
+const PARALLEL_MIN_LEN: usize = 16 * CHUNK_LEN;
+
+#[inline(always)]
+fn g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+fn compress(
+    cv: &[u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut v = [
+        cv[0],
+        cv[1],
+        cv[2],
+        cv[3],
+        cv[4],
+        cv[5],
+        cv[6],
+        cv[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut m = *block;
+
+    for r in 0..7 {
+        g(&mut v, 0, 4, 8, 12, m[0], m[1]);
+        g(&mut v, 1, 5, 9, 13, m[2], m[3]);
+        g(&mut v, 2, 6, 10, 14, m[4], m[5]);
+        g(&mut v, 3, 7, 11, 15, m[6], m[7]);
+        g(&mut v, 0, 5, 10, 15, m[8], m[9]);
+        g(&mut v, 1, 6, 11, 12, m[10], m[11]);
+        g(&mut v, 2, 7, 8, 13, m[12], m[13]);
+        g(&mut v, 3, 4, 9, 14, m[14], m[15]);
+
+        if r < 6 {
+            m = MSG_PERMUTATION.map(|i| m[i]);
+        }
+    }
+
+    for i in 0..8 {
+        v[i] ^= v[i + 8];
+        v[i + 8] ^= cv[i];
+    }
+    v
+}
+
+fn words(block: &[u8; BLOCK_LEN]) -> [u32; 16] {
+    let mut res = [0; 16];
+    for (w, x) in res.iter_mut().zip(block.chunks_exact(4)) {
+        *w = u32::from_le_bytes(x.try_into().unwrap());
+    }
+    res
+}
+
+fn first_8(x: [u32; 16]) -> [u32; 8] {
+    x[..8].try_into().unwrap()
+}
+
+// The last compression of a node, kept unevaluated so that it can be either
+// turned into a chaining value or used as the root of an extendable output.
+#[derive(Clone)]
+struct Output {
+    cv: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8(compress(
+            &self.cv,
+            &self.block,
+            self.counter,
+            self.block_len,
+            self.flags,
+        ))
+    }
+
+    fn root_block(&self, counter: u64) -> [u8; BLOCK_LEN] {
+        let words = compress(
+            &self.cv,
+            &self.block,
+            counter,
+            self.block_len,
+            self.flags | ROOT,
+        );
+        let mut res = [0; BLOCK_LEN];
+        for (x, w) in res.chunks_exact_mut(4).zip(words) {
+            x.copy_from_slice(&w.to_le_bytes());
+        }
+        res
+    }
+
+    fn parent(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> Self {
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(left);
+        block[8..].copy_from_slice(right);
+        Self {
+            cv: *key,
+            block,
+            counter: 0,
+            block_len: BLOCK_LEN as u32,
+            flags: PARENT | flags,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ChunkState {
+    cv: [u32; 8],
+    counter: u64,
+    buffer: [u8; BLOCK_LEN],
+    buffer_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: &[u32; 8], counter: u64, flags: u32) -> Self {
+        Self {
+            cv: *key,
+            counter,
+            buffer: [0; BLOCK_LEN],
+            buffer_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.buffer_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        let mut m = m;
+
+        // the last block of a chunk gets CHUNK_END, so a full block stays buffered
+        // until more input arrives
+        while !m.is_empty() {
+            if self.buffer_len == BLOCK_LEN {
+                self.cv = first_8(compress(
+                    &self.cv,
+                    &words(&self.buffer),
+                    self.counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                ));
+                self.blocks_compressed += 1;
+                self.buffer = [0; BLOCK_LEN];
+                self.buffer_len = 0;
+            }
+
+            let n = (BLOCK_LEN - self.buffer_len).min(m.len());
+            self.buffer[self.buffer_len..self.buffer_len + n].copy_from_slice(&m[..n]);
+            self.buffer_len += n;
+            m = &m[n..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            cv: self.cv,
+            block: words(&self.buffer),
+            counter: self.counter,
+            block_len: self.buffer_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+// chaining value of a non-root subtree whose first chunk is chunk number `counter`.
+// The left subtree takes the largest power-of-two number of chunks that leaves at
+// least one byte for the right one; both halves are hashed concurrently while
+// `threads` allows it.
+fn subtree_cv(m: &[u8], key: &[u32; 8], counter: u64, flags: u32, threads: usize) -> [u32; 8] {
+    if m.len() <= CHUNK_LEN {
+        let mut chunk = ChunkState::new(key, counter, flags);
+        chunk.update(m);
+        return chunk.output().chaining_value();
+    }
+
+    let (left, right) = subtree_children(m, key, counter, flags, threads);
+    Output::parent(&left, &right, key, flags).chaining_value()
+}
+
+fn subtree_children(
+    m: &[u8],
+    key: &[u32; 8],
+    counter: u64,
+    flags: u32,
+    threads: usize,
+) -> ([u32; 8], [u32; 8]) {
+    let left_len = ((m.len() - 1) / CHUNK_LEN).next_power_of_two() * CHUNK_LEN;
+    let left_len = if left_len >= m.len() {
+        left_len / 2
+    } else {
+        left_len
+    };
+    let (l, r) = m.split_at(left_len);
+    let right_counter = counter + (left_len / CHUNK_LEN) as u64;
+
+    if threads > 1 && m.len() >= PARALLEL_MIN_LEN {
+        thread::scope(|s| {
+            let left = s.spawn(|| subtree_cv(l, key, counter, flags, threads / 2));
+            let right = subtree_cv(r, key, right_counter, flags, threads - threads / 2);
+            (left.join().unwrap(), right)
+        })
+    } else {
+        (
+            subtree_cv(l, key, counter, flags, 1),
+            subtree_cv(r, key, right_counter, flags, 1),
+        )
+    }
+}
+
+// Squeezing side of BLAKE3. The output is seekable, and `squeeze` can be called any number of times.
+#[derive(Clone)]
+pub struct Blake3Reader {
+    output: Output,
+    pos: u64,
+}
+
+impl Blake3Reader {
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl XofReader for Blake3Reader {
+    fn squeeze(&mut self, out: &mut [u8]) {
+        let mut out = out;
+
+        while !out.is_empty() {
+            let block = self.output.root_block(self.pos / BLOCK_LEN as u64);
+            let offset = (self.pos % BLOCK_LEN as u64) as usize;
+            let n = (BLOCK_LEN - offset).min(out.len());
+            out[..n].copy_from_slice(&block[offset..offset + n]);
+            self.pos += n as u64;
+            out = &mut out[n..];
+        }
+    }
+}
+
+// BLAKE3 (https://github.com/BLAKE3-team/BLAKE3-specs)
+#[derive(Clone)]
+pub struct Blake3 {
+    key: [u32; 8],
+    chunk: ChunkState,
+    // chaining values of completed subtrees, largest first
+    cv_stack: Vec<[u32; 8]>,
+    flags: u32,
+    threads: usize,
+}
+
+impl Default for Blake3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blake3 {
+    // bytes
+    const BLOCK_SIZE: usize = BLOCK_LEN;
+    pub const DIGEST_SIZE: usize = 32;
+    pub const KEY_SIZE: usize = 32;
+
+    fn with_key(key: [u32; 8], flags: u32) -> Self {
+        Self {
+            key,
+            chunk: ChunkState::new(&key, 0, flags),
+            cv_stack: vec![],
+            flags,
+            threads: available_threads(),
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::with_key(IV, 0)
+    }
+
+    pub fn new_keyed(key: &[u8; Self::KEY_SIZE]) -> Self {
+        let mut block = [0; BLOCK_LEN];
+        block[..Self::KEY_SIZE].copy_from_slice(key);
+        Self::with_key(first_8(words(&block)), KEYED_HASH)
+    }
+
+    // `context` should be a hardcoded, globally unique, application-specific string
+    pub fn new_derive_key(context: &str) -> Self {
+        let mut hasher = Self::with_key(IV, DERIVE_KEY_CONTEXT);
+        hasher.update(context.as_bytes());
+        let context_key = hasher.finalize_xof().read(Self::KEY_SIZE);
+
+        let mut block = [0; BLOCK_LEN];
+        block[..Self::KEY_SIZE].copy_from_slice(&context_key);
+        Self::with_key(first_8(words(&block)), DERIVE_KEY_MATERIAL)
+    }
+
+    // upper bound on the threads used for large updates (1 disables multithreading)
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    // merges completed subtrees so that the stack matches the binary representation of `chunks`
+    fn merge_cv_stack(&mut self, chunks: u64) {
+        while self.cv_stack.len() > chunks.count_ones() as usize {
+            let right = self.cv_stack.pop().unwrap();
+            let left = self.cv_stack.pop().unwrap();
+            self.cv_stack
+                .push(Output::parent(&left, &right, &self.key, self.flags).chaining_value());
+        }
+    }
+
+    fn push_cv(&mut self, cv: [u32; 8], counter: u64) {
+        self.merge_cv_stack(counter);
+        self.cv_stack.push(cv);
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        let mut m = m;
+
+        // fill up a partially hashed chunk first
+        if self.chunk.len() > 0 {
+            let n = (CHUNK_LEN - self.chunk.len()).min(m.len());
+            self.chunk.update(&m[..n]);
+            m = &m[n..];
+            if m.is_empty() {
+                return;
+            }
+            let cv = self.chunk.output().chaining_value();
+            let counter = self.chunk.counter;
+            self.push_cv(cv, counter);
+            self.chunk = ChunkState::new(&self.key, counter + 1, self.flags);
+        }
+
+        // hash whole subtrees at once. The final chunk is always left in `self.chunk`
+        // since the root node must not be finalized here.
+        while m.len() > CHUNK_LEN {
+            let counter = self.chunk.counter;
+            let mut len = 1 << (usize::BITS - 1 - m.len().leading_zeros());
+            // a subtree must start at a multiple of its size
+            while ((len / CHUNK_LEN) as u64 - 1) & counter != 0 {
+                len /= 2;
+            }
+            let chunks = (len / CHUNK_LEN).max(1) as u64;
+
+            if len <= CHUNK_LEN {
+                self.push_cv(
+                    subtree_cv(&m[..len], &self.key, counter, self.flags, 1),
+                    counter,
+                );
+            } else {
+                let (left, right) =
+                    subtree_children(&m[..len], &self.key, counter, self.flags, self.threads);
+                self.push_cv(left, counter);
+                self.push_cv(right, counter + chunks / 2);
+            }
+
+            self.chunk = ChunkState::new(&self.key, counter + chunks, self.flags);
+            m = &m[len..];
+        }
+
+        if !m.is_empty() {
+            self.chunk.update(m);
+            // the stack is left unmerged when the input ends on a subtree boundary,
+            // see `root_output`
+            self.merge_cv_stack(self.chunk.counter);
+        }
+    }
+
+    fn root_output(&self) -> Output {
+        let mut stack = self.cv_stack.iter().rev();
+
+        let mut output = if self.chunk.len() > 0 || self.cv_stack.is_empty() {
+            self.chunk.output()
+        } else {
+            // the input ended on a subtree boundary, at least two subtrees are on the stack
+            let right = stack.next().unwrap();
+            let left = stack.next().unwrap();
+            Output::parent(left, right, &self.key, self.flags)
+        };
+
+        for left in stack {
+            output = Output::parent(left, &output.chaining_value(), &self.key, self.flags);
+        }
+        output
+    }
+
+    pub fn finalize(self) -> Blake3Digest {
+        let mut data = [0; Self::DIGEST_SIZE];
+        self.finalize_xof().squeeze(&mut data);
+
+        Blake3Digest::new(data)
+    }
+
+    pub fn finalize_xof(self) -> Blake3Reader {
+        Blake3Reader {
+            output: self.root_output(),
+            pos: 0,
+        }
+    }
+
+    pub fn hash(m: Vec<u8>) -> Result<Blake3Digest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+
+    pub fn derive_key(context: &str, material: &[u8], len: usize) -> Vec<u8> {
+        let mut hasher = Self::new_derive_key(context);
+        hasher.update(material);
+
+        hasher.finalize_xof().read(len)
+    }
+}
+
+impl HashAlgorithm for Blake3 {
+    type Output = Blake3Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Blake3::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Blake3::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Blake3::finalize(self)
+    }
+}
+
+impl ExtendableOutput for Blake3 {
+    type Reader = Blake3Reader;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+
+    fn new() -> Self {
+        Blake3::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Blake3::update(self, m);
+    }
+
+    fn finalize_xof(self) -> Self::Reader {
+        Blake3::finalize_xof(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        digest::{HashDigest, XofReader},
+        util::to_hex,
+    };
+
+    use super::Blake3;
+
+    // official test vectors (https://github.com/BLAKE3-team/BLAKE3/blob/master/test_vectors/test_vectors.json)
+    const KEY: &[u8; 32] = b"whats the Elvish word for friend";
+    const CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    fn input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn blake3_hash() {
+        let mds = [
+            (
+                0,
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (
+                1,
+                "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213",
+            ),
+            (
+                1023,
+                "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11",
+            ),
+            (
+                1024,
+                "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7",
+            ),
+            (
+                1025,
+                "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+            ),
+            (
+                2048,
+                "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a",
+            ),
+            (
+                2049,
+                "5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b6879522563030",
+            ),
+            (
+                3072,
+                "b98cb0ff3623be03326b373de6b9095218513e64f1ee2edd2525c7ad1e5cffd2",
+            ),
+            (
+                3073,
+                "7124b49501012f81cc7f11ca069ec9226cecb8a2c850cfe644e327d22d3e1cd3",
+            ),
+            (
+                4096,
+                "015094013f57a5277b59d8475c0501042c0b642e531b0a1c8f58d2163229e969",
+            ),
+            (
+                4097,
+                "9b4052b38f1c5fc8b1f9ff7ac7b27cd242487b3d890d15c96a1c25b8aa0fb995",
+            ),
+            (
+                8193,
+                "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b",
+            ),
+            (
+                31744,
+                "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c47",
+            ),
+            (
+                102400,
+                "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085",
+            ),
+        ];
+        for (len, md) in mds {
+            assert_eq!(Blake3::hash(input(len)).unwrap().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn blake3_keyed() {
+        let mds = [
+            (
+                0,
+                "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26",
+            ),
+            (
+                1025,
+                "357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea69",
+            ),
+            (
+                102400,
+                "1c35d1a5811083fd7119f5d5d1ba027b4d01c0c6c49fb6ff2cf75393ea5db4a7",
+            ),
+        ];
+        for (len, md) in mds {
+            let mut hasher = Blake3::new_keyed(KEY);
+            hasher.update(&input(len));
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn blake3_derive_key() {
+        let mds = [
+            (
+                0,
+                "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d",
+            ),
+            (
+                1025,
+                "effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb",
+            ),
+            (
+                102400,
+                "4652cff7a3f385a6103b5c260fc1593e13c778dbe608efb092fe7ee69df6e9c6",
+            ),
+        ];
+        for (len, md) in mds {
+            assert_eq!(to_hex(&Blake3::derive_key(CONTEXT, &input(len), 32)), md);
+        }
+    }
+
+    #[test]
+    fn blake3_xof() {
+        let out = "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444f4c4a22b4b399155358a994e52bf255de60035742ec71bd08ac275a1b51cc6bfe332b0ef84b409108cda080e6269ed4b";
+
+        let mut hasher = Blake3::new();
+        hasher.update(&input(1025));
+        let mut reader = hasher.finalize_xof();
+        assert_eq!(to_hex(&reader.read(80)), out);
+
+        // seeking and reading in pieces gives the same stream
+        reader.set_position(10);
+        let mut parts = vec![];
+        for len in [1, 60, 9] {
+            parts.extend(reader.read(len));
+        }
+        assert_eq!(reader.position(), 80);
+        assert_eq!(to_hex(&parts), out[20..]);
+    }
+
+    #[test]
+    fn blake3_streaming() {
+        let m = input(102400);
+        let md = "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085";
+
+        for chunk_size in [1, 63, 1023, 1024, 1025, 5000, 40000] {
+            let mut hasher = Blake3::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+
+        for threads in [1, 2, 3, 8] {
+            let mut hasher = Blake3::new();
+            hasher.set_threads(threads);
+            hasher.update(&m);
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+}
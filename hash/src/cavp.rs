@@ -0,0 +1,401 @@
+// Offline parser and runner for NIST CAVP response (.rsp) files
+// (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
+
+use std::path::Path;
+
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    util::{from_hex, to_hex},
+};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// A group of `key = value` lines separated from the others by blank lines
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RspEntry {
+    pub fields: Vec<(String, String)>,
+}
+
+impl RspEntry {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+// Entries following a run of `[key = value]` header lines
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RspSection {
+    pub header: Vec<(String, String)>,
+    pub entries: Vec<RspEntry>,
+}
+
+impl RspSection {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.header
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RspFile {
+    pub sections: Vec<RspSection>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KatReport {
+    pub passed: usize,
+    pub failed: Vec<String>,
+}
+
+impl KatReport {
+    pub fn is_ok(&self) -> bool {
+        self.passed > 0 && self.failed.is_empty()
+    }
+}
+
+fn key_value(line: &str) -> Option<(String, String)> {
+    let (k, v) = line.split_once('=')?;
+    Some((k.trim().to_string(), v.trim().to_string()))
+}
+
+fn hex_field(entry: &RspEntry, key: &str) -> Result<Vec<u8>> {
+    let v = entry
+        .get(key)
+        .ok_or_else(|| HashError::ValueError(format!("missing {}", key)))?;
+    from_hex(v).ok_or_else(|| HashError::ValueError(format!("{} is not hex: {}", key, v)))
+}
+
+impl RspFile {
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut sections: Vec<RspSection> = vec![];
+        let mut entry = RspEntry::default();
+
+        for (n, line) in s.lines().chain([""]).enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                if !entry.fields.is_empty() {
+                    if sections.is_empty() {
+                        sections.push(RspSection::default());
+                    }
+                    sections.last_mut().unwrap().entries.push(entry);
+                    entry = RspEntry::default();
+                }
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                if !entry.fields.is_empty() {
+                    return Err(HashError::ValueError(format!(
+                        "line {}: header inside a test entry",
+                        n + 1
+                    )));
+                }
+                // a header right after another header belongs to the same section
+                if sections.last().is_none_or(|x| !x.entries.is_empty()) {
+                    sections.push(RspSection::default());
+                }
+                let field = key_value(header).unwrap_or((header.trim().to_string(), String::new()));
+                sections.last_mut().unwrap().header.push(field);
+                continue;
+            }
+
+            match key_value(line) {
+                Some(field) => entry.fields.push(field),
+                None => {
+                    return Err(HashError::ValueError(format!(
+                        "line {}: expected `key = value`",
+                        n + 1
+                    )))
+                }
+            }
+        }
+
+        Ok(Self { sections })
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let s = std::fs::read_to_string(path)
+            .map_err(|e| HashError::ValueError(format!("cannot read {}: {}", path.display(), e)))?;
+        Self::parse(&s)
+    }
+}
+
+pub struct Kat {}
+
+impl Kat {
+    const MONTE_CARLO_ITERATIONS: usize = 1000;
+
+    // SHAVS gives L in bytes, SHA3VS in bits
+    fn check_output_size<H: HashAlgorithm>(section: &RspSection) -> Result<()> {
+        match section.get("L").map(|l| l.parse::<usize>()) {
+            Some(Ok(l)) if l != H::OUTPUT_SIZE && l != 8 * H::OUTPUT_SIZE => {
+                Err(HashError::ValueError(format!(
+                    "vectors are for L = {}, the algorithm outputs {} bytes",
+                    l,
+                    H::OUTPUT_SIZE
+                )))
+            }
+            Some(Err(_)) => Err(HashError::ValueError("invalid L".into())),
+            _ => Ok(()),
+        }
+    }
+
+    // ShortMsg / LongMsg files: entries of `Len`, `Msg` and `MD`
+    pub fn run_msg<H: HashAlgorithm>(rsp: &RspFile) -> Result<KatReport> {
+        let mut report = KatReport::default();
+
+        for section in &rsp.sections {
+            Self::check_output_size::<H>(section)?;
+
+            for entry in &section.entries {
+                let len: usize = entry
+                    .get("Len")
+                    .and_then(|x| x.parse().ok())
+                    .ok_or_else(|| HashError::ValueError("missing or invalid Len".into()))?;
+
+                // `Msg = 00` stands for the empty message when Len = 0, and bit-oriented
+                // files left-align the last len % 8 bits in the last byte
+                let msg = hex_field(entry, "Msg")?;
+                if msg.len() < len.div_ceil(8) {
+                    return Err(HashError::ValueError(format!(
+                        "Msg is shorter than Len = {}",
+                        len
+                    )));
+                }
+                let md = hex_field(entry, "MD")?;
+
+                let mut hasher = H::new();
+                hasher.update(&msg[..len / 8]);
+                let last = msg.get(len / 8).copied().unwrap_or(0);
+                let Some(digest) = hasher.finalize_bits(last, len % 8) else {
+                    return Err(HashError::ValueError(format!(
+                        "bit-oriented message (Len = {}) is not supported",
+                        len
+                    )));
+                };
+
+                if digest.into_bytes().as_ref() == md {
+                    report.passed += 1;
+                } else {
+                    report.failed.push(format!("Len = {}", len));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // SHA-1 / SHA-2 Monte Carlo chaining:
+    //   MD_0 = MD_1 = MD_2 = Seed
+    //   MD_i = H(MD_{i-3} || MD_{i-2} || MD_{i-1}) for i = 3, ..., 1002
+    //   checkpoint = Seed = MD_1002
+    pub fn monte_carlo<H: HashAlgorithm>(seed: &[u8], checkpoints: usize) -> Vec<Vec<u8>> {
+        let mut seed = seed.to_vec();
+        let mut res = Vec::with_capacity(checkpoints);
+
+        for _ in 0..checkpoints {
+            let mut mds = [seed.clone(), seed.clone(), seed];
+            for _ in 0..Self::MONTE_CARLO_ITERATIONS {
+                let mut hasher = H::new();
+                for md in &mds {
+                    hasher.update(md);
+                }
+                let md = hasher.finalize().digest_u8();
+                mds = [mds[1].clone(), mds[2].clone(), md];
+            }
+            seed = mds[2].clone();
+            res.push(seed.clone());
+        }
+
+        res
+    }
+
+    // Monte files: a `Seed` entry followed by `COUNT` / `MD` checkpoints
+    pub fn run_monte<H: HashAlgorithm>(rsp: &RspFile) -> Result<KatReport> {
+        let mut report = KatReport::default();
+
+        for section in &rsp.sections {
+            Self::check_output_size::<H>(section)?;
+
+            let Some((seed, checkpoints)) = section.entries.split_first() else {
+                continue;
+            };
+            let seed = hex_field(seed, "Seed")?;
+            let mds = checkpoints
+                .iter()
+                .map(|x| hex_field(x, "MD"))
+                .collect::<Result<Vec<_>>>()?;
+
+            for (i, (md, expected)) in Self::monte_carlo::<H>(&seed, mds.len())
+                .into_iter()
+                .zip(mds)
+                .enumerate()
+            {
+                if md == expected {
+                    report.passed += 1;
+                } else {
+                    report
+                        .failed
+                        .push(format!("COUNT = {}: got {}", i, to_hex(&md)));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Runs a local .rsp file, dispatching on whether it is a Monte Carlo file
+    pub fn run_file<H: HashAlgorithm>(path: impl AsRef<Path>) -> Result<KatReport> {
+        let rsp = RspFile::read(path)?;
+        let is_monte = rsp
+            .sections
+            .iter()
+            .flat_map(|x| &x.entries)
+            .any(|x| x.get("Seed").is_some());
+
+        if is_monte {
+            Self::run_monte::<H>(&rsp)
+        } else {
+            Self::run_msg::<H>(&rsp)
+        }
+    }
+}
+
+// Runs tests/vectors/<name><kind>.rsp for each kind (e.g. "ShortMsg", "Monte")
+#[cfg(test)]
+pub(crate) fn check_vectors<H: HashAlgorithm>(name: &str, kinds: &[&str]) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors");
+    for kind in kinds {
+        let path = dir.join(format!("{}{}.rsp", name, kind));
+        let report = Kat::run_file::<H>(&path).unwrap();
+        assert!(report.passed > 0, "{}", path.display());
+        assert!(report.is_ok(), "{}: {:?}", path.display(), report.failed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sha1::Sha1, sha256::Sha256, sha3::Sha3_256, sha512::Sha512};
+
+    use super::{Kat, RspFile};
+
+    const SHORT_MSG: &str = "#  CAVS 11.0
+#  \"SHA-256 ShortMsg\" information
+#  SHA-256 tests are configured for BYTE oriented implementations
+
+[L = 32]
+
+Len = 0
+Msg = 00
+MD = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+
+Len = 8
+Msg = d3
+MD = 28969cdfa74a12c82f3bad960b0b000aca2ac329deea5c2328ebc6f2ba9802c1
+
+Len = 16
+Msg = 11af
+MD = 5ca7133fa735326081558ac312c620eeca9970d1e70a4b95533d956f072d1f98
+
+Len = 32
+Msg = 74ba2521
+MD = b16aa56be3880d18cd41e68384cf1ec8c17680c45a02b1575dc1518923ae8b0e
+";
+
+    const MONTE: &str = "#  CAVS 11.1
+#  \"SHA-256 Monte\" information
+
+[L = 32]
+
+Seed = f41ece2613e4573915696b5adcd51ca328be3bf566a9ca99c9ceb0279c1cb0a7
+
+COUNT = 0
+MD = 83d28614d49c3adc1d6fc05db5f48037c056f8d2a4ce44ec6457dea5dd797cd1
+
+COUNT = 1
+MD = 99dbe3127ef2e93dd9322d6a07909eb33b63995e529b3f954b8581621bb74d39
+
+COUNT = 2
+MD = 8d4be295bb64661ca3c7efd129a2f725b33072dbdde32385b9a87b9af88ea76f
+";
+
+    #[test]
+    fn rsp_parse() {
+        let rsp = RspFile::parse(SHORT_MSG).unwrap();
+        assert_eq!(rsp.sections.len(), 1);
+        assert_eq!(rsp.sections[0].get("L"), Some("32"));
+        assert_eq!(rsp.sections[0].entries.len(), 4);
+        assert_eq!(rsp.sections[0].entries[1].get("Msg"), Some("d3"));
+
+        let rsp = RspFile::parse(MONTE).unwrap();
+        assert_eq!(rsp.sections[0].entries.len(), 4);
+        assert!(rsp.sections[0].entries[0].get("Seed").is_some());
+
+        assert!(RspFile::parse("[L = 32]\n\nLen 8\n").is_err());
+    }
+
+    #[test]
+    fn kat_msg() {
+        let rsp = RspFile::parse(SHORT_MSG).unwrap();
+        let report = Kat::run_msg::<Sha256>(&rsp).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.passed, 4);
+
+        let broken = SHORT_MSG.replace("MD = 28969c", "MD = 38969c");
+        let report = Kat::run_msg::<Sha256>(&RspFile::parse(&broken).unwrap()).unwrap();
+        assert_eq!(report.passed, 3);
+        assert_eq!(report.failed, vec!["Len = 8".to_string()]);
+
+        // the digest size in the header does not match
+        assert!(Kat::run_msg::<Sha512>(&rsp).is_err());
+        let rsp = RspFile::parse(&SHORT_MSG.replace("[L = 32]", "[L = 256]")).unwrap();
+        assert!(Kat::run_msg::<Sha256>(&rsp).unwrap().is_ok());
+
+        // bit-oriented messages go through finalize_bits
+        let bits = "[L = 32]\n\nLen = 5\nMsg = 98\nMD = 8f136783ea6f000dccc4295d4db99b648f1c8f483b27248db103ba7cd567dbba\n";
+        let report = Kat::run_msg::<Sha256>(&RspFile::parse(bits).unwrap()).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.passed, 1);
+        assert!(Kat::run_msg::<Sha3_256>(&RspFile::parse(bits).unwrap()).is_err());
+        let short = "[L = 32]\n\nLen = 24\nMsg = 9898\nMD = 00\n";
+        assert!(Kat::run_msg::<Sha256>(&RspFile::parse(short).unwrap()).is_err());
+    }
+
+    #[test]
+    fn kat_monte() {
+        let report = Kat::run_monte::<Sha256>(&RspFile::parse(MONTE).unwrap()).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.passed, 3);
+
+        let mds = Kat::monte_carlo::<Sha1>(
+            &crate::util::from_hex("dd4df644eaf3d85bace2b21accaa22b28821f5cd").unwrap(),
+            2,
+        );
+        assert_eq!(
+            crate::util::to_hex(&mds[1]),
+            "5c26de848c21586bec36995809cb02d3677423d9"
+        );
+    }
+
+    #[test]
+    fn kat_file() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("mitcrypto-kat-{}.rsp", std::process::id()));
+
+        std::fs::write(&path, MONTE).unwrap();
+        assert!(Kat::run_file::<Sha256>(&path).unwrap().is_ok());
+        std::fs::write(&path, SHORT_MSG).unwrap();
+        assert!(Kat::run_file::<Sha256>(&path).unwrap().is_ok());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(Kat::run_file::<Sha256>(dir.join("mitcrypto-no-such-file.rsp")).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    digest::{ExtendableOutput, XofReader},
+    keccak::Sponge,
+    shake::KeccakReader,
+};
+
+// Encodings of SP 800-185 section 2.3. Lengths are given in bytes and encoded in bits.
+
+// the integer x as its byte length followed by its big endian bytes
+pub fn left_encode(x: usize) -> Vec<u8> {
+    let mut res = right_encode(x);
+    res.rotate_right(1);
+    res
+}
+
+// the big endian bytes of x followed by their length
+pub fn right_encode(x: usize) -> Vec<u8> {
+    let bytes = (x as u128).to_be_bytes();
+    let n = bytes
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(bytes.len() - 1);
+
+    let mut res = bytes[n..].to_vec();
+    res.push(res.len() as u8);
+    res
+}
+
+// left_encode(bit length of s) || s
+pub fn encode_string(s: &[u8]) -> Vec<u8> {
+    let mut res = left_encode(s.len() * 8);
+    res.extend_from_slice(s);
+    res
+}
+
+// left_encode(w) || x, padded with zeros to a multiple of w bytes
+pub fn bytepad(x: &[u8], w: usize) -> Vec<u8> {
+    let mut res = left_encode(w);
+    res.extend_from_slice(x);
+    res.resize(res.len().next_multiple_of(w), 0);
+    res
+}
+
+macro_rules! cshake {
+    ($name:ident, $rate:expr) => {
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(b"", b"")
+            }
+        }
+
+        impl $name {
+            // bytes
+            const BLOCK_SIZE: usize = $rate;
+            const SUFFIX: u8 = 0x04;
+            const SHAKE_SUFFIX: u8 = 0x1f;
+
+            // `function_name` is reserved for functions defined by NIST (e.g. "KMAC"),
+            // `customization` is chosen by the application
+            pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+                // with both strings empty, cSHAKE is SHAKE
+                if function_name.is_empty() && customization.is_empty() {
+                    return Self {
+                        sponge: Sponge::new(Self::BLOCK_SIZE, Self::SHAKE_SUFFIX),
+                    };
+                }
+
+                let mut sponge = Sponge::new(Self::BLOCK_SIZE, Self::SUFFIX);
+                let mut prefix = encode_string(function_name);
+                prefix.extend(encode_string(customization));
+                sponge.absorb(&bytepad(&prefix, Self::BLOCK_SIZE));
+
+                Self { sponge }
+            }
+
+            pub fn update(&mut self, m: &[u8]) {
+                self.sponge.absorb(m);
+            }
+
+            pub fn finalize_xof(self) -> KeccakReader {
+                KeccakReader::new(self.sponge)
+            }
+
+            pub fn hash(
+                m: Vec<u8>,
+                len: usize,
+                function_name: &[u8],
+                customization: &[u8],
+            ) -> Vec<u8> {
+                let mut hasher = Self::new(function_name, customization);
+                hasher.update(&m);
+
+                hasher.finalize_xof().read(len)
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = KeccakReader;
+            const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+
+            fn new() -> Self {
+                $name::default()
+            }
+
+            fn update(&mut self, m: &[u8]) {
+                $name::update(self, m);
+            }
+
+            fn finalize_xof(self) -> Self::Reader {
+                $name::finalize_xof(self)
+            }
+        }
+    };
+}
+
+// cSHAKE128 and cSHAKE256 (SP 800-185 section 3)
+cshake!(CShake128, 168);
+cshake!(CShake256, 136);
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        shake::{Shake128, Shake256},
+        util::to_hex,
+    };
+
+    use super::{bytepad, encode_string, left_encode, right_encode, CShake128, CShake256};
+
+    #[test]
+    fn cshake_encode() {
+        assert_eq!(left_encode(0), [1, 0]);
+        assert_eq!(right_encode(0), [0, 1]);
+        assert_eq!(left_encode(168), [1, 168]);
+        assert_eq!(left_encode(256), [2, 1, 0]);
+        assert_eq!(right_encode(65536), [1, 0, 0, 3]);
+        assert_eq!(encode_string(b""), [1, 0]);
+        assert_eq!(encode_string(b"ab"), [1, 16, b'a', b'b']);
+        assert_eq!(bytepad(b"ab", 8), [1, 8, b'a', b'b', 0, 0, 0, 0]);
+        assert_eq!(bytepad(&[0; 6], 8).len(), 8);
+        assert_eq!(bytepad(&[0; 7], 8).len(), 16);
+    }
+
+    #[test]
+    fn cshake() {
+        // SP 800-185 samples
+        let m: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        assert_eq!(
+            to_hex(&CShake128::hash(
+                m[..4].to_vec(),
+                32,
+                b"",
+                b"Email Signature"
+            )),
+            "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5"
+        );
+        assert_eq!(
+            to_hex(&CShake128::hash(m.clone(), 32, b"", b"Email Signature")),
+            "c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b"
+        );
+        assert_eq!(
+            to_hex(&CShake256::hash(m[..4].to_vec(), 64, b"", b"Email Signature")),
+            "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd164020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c"
+        );
+        assert_eq!(
+            to_hex(&CShake256::hash(m.clone(), 64, b"", b"Email Signature")),
+            "07dc27b11e51fbac75bc7b3c1d983e8b4b85fb1defaf218912ac86430273091727f42b17ed1df63e8ec118f04b23633c1dfb1574c8fb55cb45da8e25afb092bb"
+        );
+
+        assert_eq!(
+            CShake128::hash(m.clone(), 100, b"", b""),
+            Shake128::hash(m.clone(), 100)
+        );
+        assert_eq!(
+            CShake256::hash(m.clone(), 100, b"", b""),
+            Shake256::hash(m, 100)
+        );
+    }
+}
@@ -1,5 +1,95 @@
 pub trait HashDigest {
     type Digest;
+    // the digest as bytes, a fixed-size array wherever the type fixes the output size
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Clone;
     fn digest(&self) -> Self::Digest;
+    fn digest_u8(&self) -> Vec<u8>;
+    fn into_bytes(self) -> Self::Bytes;
     fn hexdigest(&self) -> String;
 }
+
+pub trait HashAlgorithm: Clone {
+    type Output: HashDigest;
+    // bytes
+    const BLOCK_SIZE: usize;
+    const OUTPUT_SIZE: usize;
+
+    fn new() -> Self;
+    fn update(&mut self, m: &[u8]);
+    fn finalize(self) -> Self::Output;
+
+    // appends the `bits` (< 8) most significant bits of `last` and finalizes, or None if the
+    // algorithm only takes whole bytes
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        let _ = last;
+        (bits == 0).then(|| self.finalize())
+    }
+
+    fn digest(m: &[u8]) -> <Self::Output as HashDigest>::Bytes {
+        let mut hasher = Self::new();
+        hasher.update(m);
+        hasher.finalize().into_bytes()
+    }
+}
+
+pub trait XofReader {
+    fn squeeze(&mut self, out: &mut [u8]);
+
+    fn read(&mut self, len: usize) -> Vec<u8> {
+        let mut res = vec![0; len];
+        self.squeeze(&mut res);
+        res
+    }
+}
+
+pub trait ExtendableOutput: Clone {
+    type Reader: XofReader;
+    // bytes
+    const BLOCK_SIZE: usize;
+
+    fn new() -> Self;
+    fn update(&mut self, m: &[u8]);
+    fn finalize_xof(self) -> Self::Reader;
+
+    fn digest_xof(m: &[u8], len: usize) -> Vec<u8> {
+        let mut hasher = Self::new();
+        hasher.update(m);
+        hasher.finalize_xof().read(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sha1::Sha1, sha224::Sha224, sha256::Sha256, sha384::Sha384, sha512::Sha512,
+        sha512_224::Sha512_224, sha512_256::Sha512_256,
+    };
+
+    use super::{HashAlgorithm, HashDigest};
+
+    fn check<H: HashAlgorithm>() {
+        let mut hasher = H::new();
+        hasher.update(b"abc");
+        let md = hasher.finalize();
+
+        assert_eq!(md.digest_u8().len(), H::OUTPUT_SIZE);
+        assert_eq!(H::digest(b"abc").as_ref(), md.digest_u8());
+        let hex: String = md
+            .digest_u8()
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect();
+        assert_eq!(hex, md.hexdigest());
+    }
+
+    #[test]
+    fn hash_algorithm() {
+        check::<Sha1>();
+        check::<Sha224>();
+        check::<Sha256>();
+        check::<Sha384>();
+        check::<Sha512>();
+        check::<Sha512_224>();
+        check::<Sha512_256>();
+    }
+}
@@ -0,0 +1,14 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashError {
+    ValueError(String),
+}
+
+impl Display for HashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ValueError(e) => write!(f, "Value Error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::marker::PhantomData;
+
+use crate::{digest::HashAlgorithm, error::HashError, hmac::Hmac};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// HKDF (RFC 5869)
+#[derive(Clone)]
+pub struct Hkdf<H: HashAlgorithm> {
+    prk: Vec<u8>,
+    _hash: PhantomData<H>,
+}
+
+impl<H: HashAlgorithm> Hkdf<H> {
+    pub const MAX_OUTPUT_SIZE: usize = 255 * H::OUTPUT_SIZE;
+
+    // HKDF-Extract. An empty salt is treated as HashLen zero bytes.
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> Self {
+        let salt = if salt.is_empty() {
+            vec![0; H::OUTPUT_SIZE]
+        } else {
+            salt.to_vec()
+        };
+
+        Self {
+            prk: Hmac::<H>::mac(&salt, ikm).as_ref().to_vec(),
+            _hash: PhantomData,
+        }
+    }
+
+    pub fn from_prk(prk: &[u8]) -> Result<Self> {
+        if prk.len() < H::OUTPUT_SIZE {
+            return Err(HashError::ValueError("prk is shorter than HashLen".into()));
+        }
+
+        Ok(Self {
+            prk: prk.to_vec(),
+            _hash: PhantomData,
+        })
+    }
+
+    pub fn prk(&self) -> &[u8] {
+        &self.prk
+    }
+
+    // HKDF-Expand
+    pub fn expand(&self, info: &[u8], len: usize) -> Result<Vec<u8>> {
+        if len > Self::MAX_OUTPUT_SIZE {
+            return Err(HashError::ValueError(format!(
+                "output length must be at most 255 * HashLen ({} bytes)",
+                Self::MAX_OUTPUT_SIZE
+            )));
+        }
+
+        let mut okm = Vec::with_capacity(len + H::OUTPUT_SIZE);
+        for i in 1..=len.div_ceil(H::OUTPUT_SIZE) {
+            // T(i - 1) is the last block of the output so far, and empty for T(1)
+            let mut hmac = Hmac::<H>::new(&self.prk);
+            hmac.update(&okm[okm.len().saturating_sub(H::OUTPUT_SIZE)..]);
+            hmac.update(info);
+            hmac.update(&[i as u8]);
+            okm.extend_from_slice(hmac.finalize().as_ref());
+        }
+        okm.truncate(len);
+
+        Ok(okm)
+    }
+
+    pub fn derive(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>> {
+        Self::extract(salt, ikm).expand(info, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sha1::Sha1,
+        sha256::Sha256,
+        sha512::Sha512,
+        util::{from_hex, to_hex},
+    };
+
+    use super::Hkdf;
+
+    #[test]
+    fn hkdf() {
+        // RFC 5869 Appendix A (https://www.rfc-editor.org/rfc/rfc5869#appendix-A)
+        let hkdf = Hkdf::<Sha256>::extract(
+            &from_hex("000102030405060708090a0b0c").unwrap(),
+            &[0x0b; 22],
+        );
+        assert_eq!(
+            to_hex(hkdf.prk()),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+        assert_eq!(
+            to_hex(
+                &hkdf
+                    .expand(&from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap(), 42)
+                    .unwrap()
+            ),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+        assert_eq!(
+            to_hex(&Hkdf::<Sha256>::derive(&salt, &ikm, &info, 82).unwrap()),
+            "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87"
+        );
+
+        let hkdf = Hkdf::<Sha256>::extract(&[], &[0x0b; 22]);
+        assert_eq!(
+            to_hex(hkdf.prk()),
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04"
+        );
+        assert_eq!(
+            to_hex(&hkdf.expand(&[], 42).unwrap()),
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+        );
+
+        let hkdf = Hkdf::<Sha1>::extract(
+            &from_hex("000102030405060708090a0b0c").unwrap(),
+            &[0x0b; 11],
+        );
+        assert_eq!(
+            to_hex(hkdf.prk()),
+            "9b6c18c432a7bf8f0e71c8eb88f4b30baa2ba243"
+        );
+        assert_eq!(
+            to_hex(
+                &hkdf
+                    .expand(&from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap(), 42)
+                    .unwrap()
+            ),
+            "085a01ea1b10f36933068b56efa5ad81a4f14b822f5b091568a9cdd4f155fda2c22e422478d305f3f896"
+        );
+    }
+
+    #[test]
+    fn hkdf_output_limit() {
+        let hkdf = Hkdf::<Sha512>::extract(b"salt", b"ikm");
+        assert_eq!(hkdf.expand(b"", 255 * 64).unwrap().len(), 255 * 64);
+        assert!(hkdf.expand(b"", 255 * 64 + 1).is_err());
+
+        assert!(Hkdf::<Sha256>::from_prk(&[0; 31]).is_err());
+        let hkdf = Hkdf::<Sha256>::from_prk(&[0; 32]).unwrap();
+        assert_eq!(hkdf.expand(b"info", 0).unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    util::ct_eq,
+};
+
+// HMAC (RFC 2104)
+#[derive(Clone)]
+pub struct Hmac<H: HashAlgorithm> {
+    inner: H,
+    outer: H,
+}
+
+impl<H: HashAlgorithm> Hmac<H> {
+    const IPAD: u8 = 0x36;
+    const OPAD: u8 = 0x5c;
+
+    pub fn new(key: &[u8]) -> Self {
+        let mut k = if key.len() > H::BLOCK_SIZE {
+            H::digest(key).as_ref().to_vec()
+        } else {
+            key.to_vec()
+        };
+        k.resize(H::BLOCK_SIZE, 0);
+
+        let mut inner = H::new();
+        inner.update(&k.iter().map(|x| x ^ Self::IPAD).collect::<Vec<u8>>());
+        let mut outer = H::new();
+        outer.update(&k.iter().map(|x| x ^ Self::OPAD).collect::<Vec<u8>>());
+
+        Self { inner, outer }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.inner.update(m);
+    }
+
+    pub fn finalize(self) -> <H::Output as HashDigest>::Bytes {
+        let mut outer = self.outer;
+        outer.update(self.inner.finalize().into_bytes().as_ref());
+        outer.finalize().into_bytes()
+    }
+
+    pub fn verify(self, tag: &[u8]) -> bool {
+        ct_eq(self.finalize().as_ref(), tag)
+    }
+
+    // RFC 2104 section 5: a truncated tag keeps at least half of the output and at least 80 bits
+    pub fn verify_truncated(self, tag: &[u8]) -> bool {
+        if tag.len() < (H::OUTPUT_SIZE / 2).max(10) || tag.len() > H::OUTPUT_SIZE {
+            return false;
+        }
+        ct_eq(&self.finalize().as_ref()[..tag.len()], tag)
+    }
+
+    pub fn mac(key: &[u8], m: &[u8]) -> <H::Output as HashDigest>::Bytes {
+        let mut hmac = Self::new(key);
+        hmac.update(m);
+        hmac.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        digest::HashAlgorithm,
+        sha1::Sha1,
+        sha224::Sha224,
+        sha256::Sha256,
+        sha384::Sha384,
+        sha512::Sha512,
+        sha512_224::Sha512_224,
+        sha512_256::Sha512_256,
+        util::{from_hex, to_hex},
+    };
+
+    use super::Hmac;
+
+    // (key, data) of test cases 1, 2, 3, 4 and 6 in RFC 2202 / RFC 4231
+    fn cases() -> Vec<(Vec<u8>, Vec<u8>)> {
+        vec![
+            (vec![0x0b; 20], b"Hi There".to_vec()),
+            (b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec()),
+            (vec![0xaa; 20], vec![0xdd; 50]),
+            (
+                from_hex("0102030405060708090a0b0c0d0e0f10111213141516171819").unwrap(),
+                vec![0xcd; 50],
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+            ),
+        ]
+    }
+
+    fn check<H: HashAlgorithm>(tags: [&str; 5]) {
+        for ((key, m), tag) in cases().into_iter().zip(tags) {
+            assert_eq!(to_hex(Hmac::<H>::mac(&key, &m).as_ref()), tag);
+
+            let mut hmac = Hmac::<H>::new(&key);
+            for chunk in m.chunks(7) {
+                hmac.update(chunk);
+            }
+            assert!(hmac.verify(&from_hex(tag).unwrap()));
+        }
+    }
+
+    #[test]
+    fn hmac_sha1() {
+        // RFC 2202 (https://www.rfc-editor.org/rfc/rfc2202)
+        check::<Sha1>([
+            "b617318655057264e28bc0b6fb378c8ef146be00",
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            "125d7342b9ac11cd91a39af48aa17b4f63f175d3",
+            "4c9007f4026250c6bc8414f9bf50c86c2d7235da",
+            "90d0dace1c1bdc957339307803160335bde6df2b",
+        ]);
+    }
+
+    #[test]
+    fn hmac_sha2() {
+        // RFC 4231 (https://www.rfc-editor.org/rfc/rfc4231)
+        check::<Sha224>([
+            "896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22",
+            "a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44",
+            "7fb3cb3588c6c1f6ffa9694d7d6ad2649365b0c1f65d69d1ec8333ea",
+            "6c11506874013cac6a2abc1bb382627cec6a90d86efc012de7afec5a",
+            "95e9a0db962095adaebe9b2d6f0dbce2d499f112f2d2b7273fa6870e",
+        ]);
+        check::<Sha256>([
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+        ]);
+        check::<Sha384>([
+            "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6",
+            "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e8e2240ca5e69e2c78b3239ecfab21649",
+            "88062608d3e6ad8a0aa2ace014c8a86f0aa635d947ac9febe83ef4e55966144b2a5ab39dc13814b94e3ab6e101a34f27",
+            "3e8a69b7783c25851933ab6290af6ca77a9981480850009cc5577c6e1f573b4e6801dd23c4a7d679ccf8a386c674cffb",
+            "4ece084485813e9088d2c63a041bc5b44f9ef1012a2b588f3cd11f05033ac4c60c2ef6ab4030fe8296248df163f44952",
+        ]);
+        check::<Sha512>([
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb",
+            "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd",
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+        ]);
+    }
+
+    #[test]
+    fn hmac_sha512_t() {
+        check::<Sha512_224>([
+            "b244ba01307c0e7a8ccaad13b1067a4cf6b961fe0c6a20bda3d92039",
+            "4a530b31a79ebcce36916546317c45f247d83241dfb818fd37254bde",
+            "db34ea525c2c216ee5a6ccb6608bea870bbef12fd9b96a5109e2b6fc",
+            "c2391863cda465c6828af06ac5d4b72d0b792109952da530e11a0d26",
+            "29bef8ce88b54d4226c3c7718ea9e32ace2429026f089e38cea9aeda",
+        ]);
+        check::<Sha512_256>([
+            "9f9126c3d9c3c330d760425ca8a217e31feae31bfe70196ff81642b868402eab",
+            "6df7b24630d5ccb2ee335407081a87188c221489768fa2020513b2d593359456",
+            "229006391d66c8ecddf43ba5cf8f83530ef221a4e9401840d1bead5137c8a2ea",
+            "36d60c8aa1d0be856e10804cf836e821e8733cbafeae87630589fd0b9b0a2f4c",
+            "87123c45f7c537a404f8f47cdbedda1fc9bec60eeb971982ce7ef10e774e6539",
+        ]);
+    }
+
+    #[test]
+    fn hmac_truncated() {
+        // RFC 4231 test case 5
+        let mut hmac = Hmac::<Sha256>::new(&[0x0c; 20]);
+        hmac.update(b"Test With Truncation");
+        let tag = from_hex("a3b6167473100ee06e0c796c2955552b").unwrap();
+
+        assert!(hmac.clone().verify_truncated(&tag));
+        assert!(!hmac.clone().verify(&tag));
+        assert!(!hmac.clone().verify_truncated(&tag[..8]));
+
+        let mut forged = tag.clone();
+        forged[15] ^= 1;
+        assert!(!hmac.verify_truncated(&forged));
+    }
+}
@@ -0,0 +1,223 @@
+// std::io adapters, so that files and sockets can be hashed without reading them into memory:
+// the hashers implement `Write` (use them with `std::io::copy`), `hash_reader` drains a
+// `Read`, and `Tee` hashes the data that passes through a reader or writer.
+
+use std::io::{self, Read, Write};
+
+use crate::{
+    blake2::{Blake2b, Blake2s},
+    blake3::Blake3,
+    cshake::{CShake128, CShake256},
+    digest::HashAlgorithm,
+    sha1::Sha1,
+    sha224::Sha224,
+    sha256::Sha256,
+    sha3::{Keccak256, Sha3_224, Sha3_256, Sha3_384, Sha3_512},
+    sha384::Sha384,
+    sha512::Sha512,
+    sha512_224::Sha512_224,
+    sha512_256::Sha512_256,
+    sha512_t::Sha512T,
+    shake::{Shake128, Shake256},
+    sm3::Sm3,
+};
+#[cfg(feature = "legacy")]
+use crate::{md5::Md5, ripemd160::Ripemd160};
+
+const BUFFER_SIZE: usize = 1 << 13;
+
+macro_rules! impl_write {
+    ($($name:ty),* $(,)?) => {
+        $(
+            impl Write for $name {
+                fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                    self.update(buf);
+                    Ok(buf.len())
+                }
+
+                fn flush(&mut self) -> io::Result<()> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_write!(
+    Sha1, Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256, Sha3_224, Sha3_256, Sha3_384,
+    Sha3_512, Keccak256, Shake128, Shake256, CShake128, CShake256, Blake2b, Blake2s, Blake3, Sm3,
+);
+#[cfg(feature = "legacy")]
+impl_write!(Md5, Ripemd160);
+
+impl<const T: usize> Write for Sha512T<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// hashes everything `reader` yields until EOF
+pub fn hash_reader<H: HashAlgorithm, R: Read>(mut reader: R) -> io::Result<H::Output> {
+    let mut hasher = H::new();
+    let mut buf = vec![0; BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(hasher.finalize())
+}
+
+// Passes data through `inner` and hashes it on the way: the bytes read from it when it is a
+// reader, or the bytes accepted by it when it is a writer.
+pub struct Tee<T, H: HashAlgorithm> {
+    inner: T,
+    hasher: H,
+}
+
+impl<T, H: HashAlgorithm> Tee<T, H> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: H::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    // digest of the data passed through so far
+    pub fn digest(&self) -> H::Output {
+        self.hasher.clone().finalize()
+    }
+
+    pub fn finalize(self) -> (T, H::Output) {
+        (self.inner, self.hasher.finalize())
+    }
+}
+
+impl<T: Read, H: HashAlgorithm> Read for Tee<T, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<T: Write, H: HashAlgorithm> Write for Tee<T, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // only the part the writer took is hashed
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    use crate::{
+        blake3::Blake3,
+        digest::{ExtendableOutput, HashDigest, XofReader},
+        sha256::Sha256,
+        sha512::Sha512,
+        shake::Shake128,
+    };
+
+    use super::{hash_reader, Tee};
+
+    // a reader that hands out at most 7 bytes per call
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn io_write() {
+        let m: Vec<u8> = (0..20000).map(|i| (i % 251) as u8).collect();
+
+        let mut hasher = Sha256::new();
+        io::copy(&mut m.as_slice(), &mut hasher).unwrap();
+        assert_eq!(
+            hasher.finalize().hexdigest(),
+            Sha256::hash(m.clone()).unwrap().hexdigest()
+        );
+
+        let mut hasher = Blake3::new();
+        let n = 12345;
+        write!(hasher, "n = {}", n).unwrap();
+        assert_eq!(
+            hasher.finalize().hexdigest(),
+            Blake3::hash(b"n = 12345".to_vec()).unwrap().hexdigest()
+        );
+
+        let mut hasher = Shake128::new();
+        hasher.write_all(&m).unwrap();
+        assert_eq!(hasher.finalize_xof().read(64), Shake128::digest_xof(&m, 64));
+    }
+
+    #[test]
+    fn io_hash_reader() {
+        let m: Vec<u8> = (0..20000).map(|i| (i % 251) as u8).collect();
+
+        assert_eq!(
+            hash_reader::<Sha512, _>(m.as_slice()).unwrap().hexdigest(),
+            Sha512::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert_eq!(
+            hash_reader::<Sha256, _>(Trickle(&m)).unwrap().hexdigest(),
+            Sha256::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert_eq!(
+            hash_reader::<Sha256, _>(io::empty()).unwrap().hexdigest(),
+            Sha256::hash(vec![]).unwrap().hexdigest()
+        );
+    }
+
+    #[test]
+    fn io_tee() {
+        let m: Vec<u8> = (0..20000).map(|i| (i % 251) as u8).collect();
+        let md = Sha256::hash(m.clone()).unwrap().hexdigest();
+
+        // reading through the tee
+        let mut tee = Tee::<_, Sha256>::new(Trickle(&m));
+        let mut out = vec![];
+        tee.read_to_end(&mut out).unwrap();
+        let (_, res) = tee.finalize();
+        assert_eq!(out, m);
+        assert_eq!(res.hexdigest(), md);
+
+        // writing through the tee
+        let mut tee = Tee::<_, Sha256>::new(vec![]);
+        io::copy(&mut m.as_slice(), &mut tee).unwrap();
+        assert_eq!(tee.digest().hexdigest(), md);
+        let (out, res) = tee.finalize();
+        assert_eq!(out, m);
+        assert_eq!(res.hexdigest(), md);
+    }
+}
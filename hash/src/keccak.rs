@@ -0,0 +1,185 @@
+pub struct Keccak {}
+
+impl Keccak {
+    const ROUNDS: usize = 24;
+    const RC: [u64; Self::ROUNDS] = [
+        0x0000000000000001,
+        0x0000000000008082,
+        0x800000000000808a,
+        0x8000000080008000,
+        0x000000000000808b,
+        0x0000000080000001,
+        0x8000000080008081,
+        0x8000000000008009,
+        0x000000000000008a,
+        0x0000000000000088,
+        0x0000000080008009,
+        0x000000008000000a,
+        0x000000008000808b,
+        0x800000000000008b,
+        0x8000000000008089,
+        0x8000000000008003,
+        0x8000000000008002,
+        0x8000000000000080,
+        0x000000000000800a,
+        0x800000008000000a,
+        0x8000000080008081,
+        0x8000000000008080,
+        0x0000000080000001,
+        0x8000000080008008,
+    ];
+    // rotation offsets of the rho step, indexed by x + 5y
+    const RHO: [u32; 25] = [
+        0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56,
+        14,
+    ];
+
+    // Keccak-f[1600] on the state A[x, y] = a[x + 5y]
+    pub fn f1600(a: &mut [u64; 25]) {
+        for rc in Self::RC {
+            // theta
+            let mut c = [0u64; 5];
+            for x in 0..5 {
+                c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+            }
+            for x in 0..5 {
+                let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+                for y in 0..5 {
+                    a[x + 5 * y] ^= d;
+                }
+            }
+
+            // rho and pi
+            let mut b = [0u64; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                        a[x + 5 * y].rotate_left(Self::RHO[x + 5 * y]);
+                }
+            }
+
+            // chi
+            for y in 0..5 {
+                for x in 0..5 {
+                    a[x + 5 * y] =
+                        b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+                }
+            }
+
+            // iota
+            a[0] ^= rc;
+        }
+    }
+}
+
+// Sponge construction over Keccak-f[1600] with pad10*1.
+// `suffix` holds the domain separation bits followed by the first padding bit
+// (e.g. 0x06 for SHA-3, 0x1f for SHAKE and 0x01 for the original Keccak).
+#[derive(Clone)]
+pub struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    suffix: u8,
+    pos: usize,
+    squeezing: bool,
+}
+
+impl Sponge {
+    // `rate` is in bytes
+    pub fn new(rate: usize, suffix: u8) -> Self {
+        assert!(
+            0 < rate && rate < 200 && rate.is_multiple_of(8),
+            "rate must be a positive multiple of 8 below 200 bytes"
+        );
+
+        Self {
+            state: [0; 25],
+            rate,
+            suffix,
+            pos: 0,
+            squeezing: false,
+        }
+    }
+
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn xor_byte(&mut self, i: usize, x: u8) {
+        self.state[i / 8] ^= (x as u64) << (8 * (i % 8));
+    }
+
+    fn byte(&self, i: usize) -> u8 {
+        (self.state[i / 8] >> (8 * (i % 8))) as u8
+    }
+
+    pub fn absorb(&mut self, m: &[u8]) {
+        assert!(!self.squeezing, "cannot absorb after squeezing");
+
+        for &x in m {
+            self.xor_byte(self.pos, x);
+            self.pos += 1;
+            if self.pos == self.rate {
+                Keccak::f1600(&mut self.state);
+                self.pos = 0;
+            }
+        }
+    }
+
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        if !self.squeezing {
+            self.xor_byte(self.pos, self.suffix);
+            self.xor_byte(self.rate - 1, 0x80);
+            Keccak::f1600(&mut self.state);
+            self.pos = 0;
+            self.squeezing = true;
+        }
+
+        for x in out.iter_mut() {
+            if self.pos == self.rate {
+                Keccak::f1600(&mut self.state);
+                self.pos = 0;
+            }
+            *x = self.byte(self.pos);
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keccak, Sponge};
+
+    #[test]
+    fn keccak_f1600() {
+        // Keccak team's KeccakF-1600 intermediate values, state after the first permutation of zeros
+        let mut a = [0u64; 25];
+        Keccak::f1600(&mut a);
+        assert_eq!(a[0], 0xf1258f7940e1dde7);
+        assert_eq!(a[1], 0x84d5ccf933c0478a);
+        assert_eq!(a[24], 0xeaf1ff7b5ceca249);
+
+        Keccak::f1600(&mut a);
+        assert_eq!(a[0], 0x2d5c954df96ecb3c);
+        assert_eq!(a[24], 0x20d06cd26a8fbf5c);
+    }
+
+    #[test]
+    fn sponge() {
+        let mut one = Sponge::new(136, 0x06);
+        one.absorb(&[0x61; 300]);
+        let mut out = [0u8; 400];
+        one.squeeze(&mut out);
+
+        let mut two = Sponge::new(136, 0x06);
+        for chunk in [0x61; 300].chunks(17) {
+            two.absorb(chunk);
+        }
+        let mut parts = [0u8; 400];
+        for chunk in parts.chunks_mut(33) {
+            two.squeeze(chunk);
+        }
+
+        assert_eq!(out, parts);
+    }
+}
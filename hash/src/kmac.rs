@@ -0,0 +1,139 @@
+use crate::{
+    cshake::{bytepad, encode_string, right_encode, CShake128, CShake256},
+    digest::XofReader,
+    shake::KeccakReader,
+    util::ct_eq,
+};
+
+macro_rules! kmac {
+    ($name:ident, $cshake:ident, $rate:expr) => {
+        #[derive(Clone)]
+        pub struct $name {
+            inner: $cshake,
+        }
+
+        impl $name {
+            // bytes
+            const BLOCK_SIZE: usize = $rate;
+            // SP 800-185 section 8.4.2: tags shorter than 32 bits are not accepted
+            pub const MIN_TAG_SIZE: usize = 4;
+
+            pub fn new(key: &[u8], customization: &[u8]) -> Self {
+                let mut inner = $cshake::new(b"KMAC", customization);
+                inner.update(&bytepad(&encode_string(key), Self::BLOCK_SIZE));
+
+                Self { inner }
+            }
+
+            pub fn update(&mut self, m: &[u8]) {
+                self.inner.update(m);
+            }
+
+            // `len` bytes of tag. The length is bound into the tag, so a shorter tag is
+            // not a prefix of a longer one.
+            pub fn finalize(mut self, len: usize) -> Vec<u8> {
+                self.inner.update(&right_encode(len * 8));
+                self.inner.finalize_xof().read(len)
+            }
+
+            // KMACXOF: output of arbitrary length, decided while reading
+            pub fn finalize_xof(mut self) -> KeccakReader {
+                self.inner.update(&right_encode(0));
+                self.inner.finalize_xof()
+            }
+
+            pub fn verify(self, tag: &[u8]) -> bool {
+                if tag.len() < Self::MIN_TAG_SIZE {
+                    return false;
+                }
+                ct_eq(&self.finalize(tag.len()), tag)
+            }
+
+            pub fn mac(key: &[u8], m: &[u8], len: usize, customization: &[u8]) -> Vec<u8> {
+                let mut kmac = Self::new(key, customization);
+                kmac.update(m);
+                kmac.finalize(len)
+            }
+        }
+    };
+}
+
+// KMAC128 and KMAC256 (SP 800-185 section 4)
+kmac!(Kmac128, CShake128, 168);
+kmac!(Kmac256, CShake256, 136);
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::XofReader, util::to_hex};
+
+    use super::{Kmac128, Kmac256};
+
+    fn key() -> Vec<u8> {
+        (0x40..0x60).collect()
+    }
+
+    #[test]
+    fn kmac() {
+        // SP 800-185 samples
+        let m: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        assert_eq!(
+            to_hex(&Kmac128::mac(&key(), &m[..4], 32, b"")),
+            "e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"
+        );
+        assert_eq!(
+            to_hex(&Kmac128::mac(&key(), &m[..4], 32, b"My Tagged Application")),
+            "3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5"
+        );
+        assert_eq!(
+            to_hex(&Kmac128::mac(&key(), &m, 32, b"My Tagged Application")),
+            "1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230"
+        );
+        assert_eq!(
+            to_hex(&Kmac256::mac(&key(), &m[..4], 64, b"My Tagged Application")),
+            "20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd"
+        );
+        assert_eq!(
+            to_hex(&Kmac256::mac(&key(), &m, 64, b"")),
+            "75358cf39e41494e949707927cee0af20a3ff553904c86b08f21cc414bcfd691589d27cf5e15369cbbff8b9a4c2eb17800855d0235ff635da82533ec6b759b69"
+        );
+
+        let mut kmac = Kmac128::new(&key(), b"");
+        kmac.update(&m[..2]);
+        kmac.update(&m[2..4]);
+        let tag = Kmac128::mac(&key(), &m[..4], 32, b"");
+        assert!(kmac.clone().verify(&tag));
+        // the requested length is part of the input, so a truncated tag does not verify
+        assert!(!kmac.clone().verify(&tag[..31]));
+
+        // too short to be a MAC, even when computed for that length
+        assert!(!kmac.clone().verify(&[]));
+        let short = Kmac128::mac(&key(), &m[..4], 3, b"");
+        assert!(!kmac.clone().verify(&short));
+        let tag = Kmac128::mac(&key(), &m[..4], 4, b"");
+        assert!(kmac.verify(&tag));
+        assert!(!Kmac256::new(&key(), b"").verify(&[]));
+    }
+
+    #[test]
+    fn kmac_xof() {
+        // SP 800-185 samples
+        let m: Vec<u8> = (0..200).map(|i| i as u8).collect();
+
+        let mut kmac = Kmac128::new(&key(), b"");
+        kmac.update(&m[..4]);
+        assert_eq!(
+            to_hex(&kmac.finalize_xof().read(32)),
+            "cd83740bbd92ccc8cf032b1481a0f4460e7ca9dd12b08a0c4031178bacd6ec35"
+        );
+
+        let mut kmac = Kmac256::new(&key(), b"My Tagged Application");
+        kmac.update(&m);
+        let mut reader = kmac.finalize_xof();
+        let mut out = reader.read(10);
+        out.extend(reader.read(54));
+        assert_eq!(
+            to_hex(&out),
+            "d5be731c954ed7732846bb59dbe3a8e30f83e77a4bff4459f2f1c2b4ecebb8ce67ba01c62e8ab8578d2d499bd1bb276768781190020a306a97de281dcc30305d"
+        );
+    }
+}
@@ -1,8 +1,38 @@
+pub mod accel;
+pub mod argon2;
+pub mod attacks;
+pub mod batch;
+pub mod bcrypt;
+pub mod blake2;
+pub mod blake3;
+pub mod cavp;
+pub mod cshake;
 pub mod digest;
+pub mod error;
+pub mod hkdf;
+pub mod hmac;
+pub mod io;
+pub mod keccak;
+pub mod kmac;
+#[cfg(feature = "legacy")]
+pub mod md5;
+pub mod merkle;
+pub mod parallelhash;
+pub mod pbkdf2;
+#[cfg(feature = "legacy")]
+pub mod ripemd160;
+pub mod scrypt;
 pub mod sha1;
 pub mod sha224;
 pub mod sha256;
+pub mod sha3;
 pub mod sha384;
 pub mod sha512;
 pub mod sha512_224;
 pub mod sha512_256;
+pub mod sha512_t;
+pub mod sha_crypt;
+pub mod shake;
+pub mod sm3;
+pub mod tuplehash;
+pub mod util;
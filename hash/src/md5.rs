@@ -0,0 +1,238 @@
+// MD5 (RFC 1321)
+// Collisions are practical: only use it to interoperate with existing data, never for security.
+
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    util::words_to_bytes,
+};
+
+pub struct Md5Digest {
+    data: <Md5Digest as HashDigest>::Digest,
+}
+
+impl Md5Digest {
+    fn new(data: <Md5Digest as HashDigest>::Digest) -> Self {
+        Self { data }
+    }
+}
+
+impl HashDigest for Md5Digest {
+    type Digest = [u32; 4];
+    type Bytes = [u8; 16];
+
+    fn digest(&self) -> Self::Digest {
+        self.data
+    }
+
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_le_bytes)
+    }
+
+    fn hexdigest(&self) -> String {
+        let mut res = String::new();
+        for i in self.digest_u8() {
+            res.push_str(&format!("{:0>2x?}", i));
+        }
+        res
+    }
+}
+
+#[derive(Clone)]
+pub struct Md5 {
+    hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Md5 {
+    // bytes
+    const BLOCK_SIZE: usize = 64;
+    const WORD_SIZE: usize = 4;
+    pub const DIGEST_SIZE: usize = 16;
+    const IV: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE] =
+        [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    // floor(2^32 * abs(sin(i + 1)))
+    const K: [u32; 64] = [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613,
+        0xfd469501, 0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193,
+        0xa679438e, 0x49b40821, 0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d,
+        0x02441453, 0xd8a1e681, 0xe7d3fbc8, 0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+        0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a, 0xfffa3942, 0x8771f681, 0x6d9d6122,
+        0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70, 0x289b7ec6, 0xeaa127fa,
+        0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665, 0xf4292244,
+        0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb,
+        0xeb86d391,
+    ];
+    const S: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+    // `m` is the unprocessed tail of a message whose total length is `l` bytes
+    fn pad(m: Vec<u8>, l: usize) -> Vec<u8> {
+        let mods = (l + 1) % Self::BLOCK_SIZE;
+        let k = if mods > 56 {
+            Self::BLOCK_SIZE - (mods - 56)
+        } else {
+            56 - mods
+        };
+        let mut res = m;
+        res.push(0b10000000);
+        res.extend(vec![0x0_u8; k]);
+        res.extend_from_slice(&((l as u64).wrapping_mul(8)).to_le_bytes());
+        res
+    }
+
+    fn compress(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        let mut x = [0u32; 16];
+        for (w, b) in x.iter_mut().zip(block.chunks_exact(Self::WORD_SIZE)) {
+            *w = u32::from_le_bytes(b.try_into().unwrap());
+        }
+
+        let [mut a, mut b, mut c, mut d] = *hs;
+        for t in 0..64 {
+            let (f, g) = match t / 16 {
+                0 => ((b & c) | (!b & d), t),
+                1 => ((d & b) | (!d & c), (5 * t + 1) % 16),
+                2 => (b ^ c ^ d, (3 * t + 5) % 16),
+                _ => (c ^ (b | !d), (7 * t) % 16),
+            };
+            let tt = a
+                .wrapping_add(f)
+                .wrapping_add(Self::K[t])
+                .wrapping_add(x[g])
+                .rotate_left(Self::S[4 * (t / 16) + t % 4]);
+
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(tt);
+        }
+        hs[0] = hs[0].wrapping_add(a);
+        hs[1] = hs[1].wrapping_add(b);
+        hs[2] = hs[2].wrapping_add(c);
+        hs[3] = hs[3].wrapping_add(d);
+    }
+
+    pub fn new() -> Self {
+        Self {
+            hs: Self::IV,
+            buffer: Vec::with_capacity(Self::BLOCK_SIZE),
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.len += m.len();
+
+        let mut m = m;
+        if !self.buffer.is_empty() {
+            let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+            self.buffer.extend_from_slice(&m[..n]);
+            m = &m[n..];
+            if self.buffer.len() < Self::BLOCK_SIZE {
+                return;
+            }
+            Self::compress(&mut self.hs, self.buffer.as_slice().try_into().unwrap());
+            self.buffer.clear();
+        }
+
+        let mut blocks = m.chunks_exact(Self::BLOCK_SIZE);
+        for block in &mut blocks {
+            Self::compress(&mut self.hs, block.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalize(self) -> Md5Digest {
+        let mut hs = self.hs;
+        for block in Self::pad(self.buffer, self.len).chunks_exact(Self::BLOCK_SIZE) {
+            Self::compress(&mut hs, block.try_into().unwrap());
+        }
+
+        Md5Digest::new(hs)
+    }
+
+    pub fn hash(m: Vec<u8>) -> Result<Md5Digest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+}
+
+impl HashAlgorithm for Md5 {
+    type Output = Md5Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Md5::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Md5::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Md5::finalize(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashDigest, hmac::Hmac, md5::Md5, util::to_hex};
+
+    #[test]
+    fn md5() {
+        // RFC 1321 test suite
+        let cases = [
+            ("", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+            ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a",
+            ),
+        ];
+        for (m, md) in cases {
+            assert_eq!(Md5::hash(m.as_bytes().to_vec()).unwrap().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn md5_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        for chunk_size in [1, 7, 63, 64, 65, 300] {
+            let mut hasher = Md5::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finalize().hexdigest(),
+                "a24f1e3ef66950e1327f210e3997ba2c"
+            );
+        }
+    }
+
+    #[test]
+    fn hmac_md5() {
+        // RFC 2104 appendix
+        assert_eq!(
+            to_hex(&Hmac::<Md5>::mac(&[0x0b; 16], b"Hi There")),
+            "9294727a3638bb1c13f48ef8158bfc9d"
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use std::marker::PhantomData;
+
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// Merkle hash tree (RFC 6962 section 2.1), as used by transparency logs.
+// Leaves and interior nodes are domain separated by a 0x00 / 0x01 prefix, and a tree of
+// n leaves is split at the largest power of two smaller than n.
+#[derive(Clone)]
+pub struct MerkleTree<H: HashAlgorithm> {
+    // levels[0] holds the leaf hashes. A node without a sibling is carried up unchanged,
+    // which gives the same tree as the recursive definition of RFC 6962.
+    levels: Vec<Vec<Vec<u8>>>,
+    _hash: PhantomData<H>,
+}
+
+impl<H: HashAlgorithm> Default for MerkleTree<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: HashAlgorithm> MerkleTree<H> {
+    const LEAF_PREFIX: u8 = 0x00;
+    const NODE_PREFIX: u8 = 0x01;
+
+    pub fn new() -> Self {
+        Self {
+            levels: vec![vec![]],
+            _hash: PhantomData,
+        }
+    }
+
+    pub fn from_leaves<I, T>(leaves: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut res = Self::new();
+        for leaf in leaves {
+            res.push(leaf.as_ref());
+        }
+        res
+    }
+
+    // MTH({d}) = H(0x00 || d)
+    pub fn leaf_hash(leaf: &[u8]) -> Vec<u8> {
+        let mut hasher = H::new();
+        hasher.update(&[Self::LEAF_PREFIX]);
+        hasher.update(leaf);
+        hasher.finalize().digest_u8()
+    }
+
+    // MTH(D[n]) = H(0x01 || MTH(D[0:k]) || MTH(D[k:n]))
+    pub fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+        let mut hasher = H::new();
+        hasher.update(&[Self::NODE_PREFIX]);
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().digest_u8()
+    }
+
+    // MTH({}) = H()
+    pub fn empty_root() -> Vec<u8> {
+        H::digest(&[]).as_ref().to_vec()
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // appends a leaf and returns its index
+    pub fn push(&mut self, leaf: &[u8]) -> usize {
+        self.push_hash(Self::leaf_hash(leaf))
+    }
+
+    // appends an already hashed leaf (`leaf_hash`) and returns its index
+    pub fn push_hash(&mut self, leaf_hash: Vec<u8>) -> usize {
+        let index = self.len();
+        self.levels[0].push(leaf_hash);
+
+        // only the nodes on the path from the new leaf to the root change
+        let mut i = index;
+        let mut l = 0;
+        while self.levels[l].len() > 1 {
+            let parent = if i % 2 == 1 {
+                Self::node_hash(&self.levels[l][i - 1], &self.levels[l][i])
+            } else {
+                self.levels[l][i].clone()
+            };
+
+            if self.levels.len() == l + 1 {
+                self.levels.push(vec![]);
+            }
+            let next = &mut self.levels[l + 1];
+            if next.len() > i / 2 {
+                next[i / 2] = parent;
+            } else {
+                next.push(parent);
+            }
+
+            i /= 2;
+            l += 1;
+        }
+
+        index
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<&[u8]> {
+        self.levels[0].get(index).map(|x| x.as_slice())
+    }
+
+    pub fn root(&self) -> Vec<u8> {
+        self.subtree_root(0, self.len())
+    }
+
+    // root of the tree made of the first `size` leaves
+    pub fn root_at(&self, size: usize) -> Result<Vec<u8>> {
+        self.check_size(size)?;
+        Ok(self.subtree_root(0, size))
+    }
+
+    // audit path of the leaf `index` in the tree of the first `size` leaves (RFC 6962 section 2.1.1)
+    pub fn inclusion_proof(&self, index: usize, size: usize) -> Result<Vec<Vec<u8>>> {
+        self.check_size(size)?;
+        if index >= size {
+            return Err(HashError::ValueError(format!(
+                "leaf index {} is out of range for a tree of size {}",
+                index, size
+            )));
+        }
+
+        let mut res = vec![];
+        self.path(index, 0, size, &mut res);
+        Ok(res)
+    }
+
+    // proof that the tree of the first `new_size` leaves extends the one of the first
+    // `old_size` leaves (RFC 6962 section 2.1.2)
+    pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Result<Vec<Vec<u8>>> {
+        self.check_size(new_size)?;
+        if old_size == 0 || old_size > new_size {
+            return Err(HashError::ValueError(format!(
+                "old size must satisfy 0 < old size <= new size, got {} and {}",
+                old_size, new_size
+            )));
+        }
+
+        let mut res = vec![];
+        self.subproof(old_size, 0, new_size, true, &mut res);
+        Ok(res)
+    }
+
+    // RFC 9162 section 2.1.3.2
+    pub fn verify_inclusion(
+        leaf: &[u8],
+        index: usize,
+        size: usize,
+        proof: &[Vec<u8>],
+        root: &[u8],
+    ) -> bool {
+        if index >= size {
+            return false;
+        }
+
+        let (mut fi, mut si) = (index, size - 1);
+        let mut r = Self::leaf_hash(leaf);
+        for p in proof {
+            if si == 0 {
+                return false;
+            }
+            if fi % 2 == 1 || fi == si {
+                r = Self::node_hash(p, &r);
+                while fi % 2 == 0 && fi != 0 {
+                    fi >>= 1;
+                    si >>= 1;
+                }
+            } else {
+                r = Self::node_hash(&r, p);
+            }
+            fi >>= 1;
+            si >>= 1;
+        }
+
+        si == 0 && r == root
+    }
+
+    // RFC 9162 section 2.1.4.2
+    pub fn verify_consistency(
+        old_size: usize,
+        new_size: usize,
+        old_root: &[u8],
+        new_root: &[u8],
+        proof: &[Vec<u8>],
+    ) -> bool {
+        if old_size == 0 || old_size > new_size {
+            return false;
+        }
+        if old_size == new_size {
+            return proof.is_empty() && old_root == new_root;
+        }
+
+        // the old root is a node of the new tree and is left out of the proof
+        let mut path: Vec<&[u8]> = vec![];
+        if old_size.is_power_of_two() {
+            path.push(old_root);
+        }
+        path.extend(proof.iter().map(|x| x.as_slice()));
+        if path.is_empty() {
+            return false;
+        }
+
+        let (mut fi, mut si) = (old_size - 1, new_size - 1);
+        while fi % 2 == 1 {
+            fi >>= 1;
+            si >>= 1;
+        }
+
+        let mut fr = path[0].to_vec();
+        let mut sr = path[0].to_vec();
+        for c in &path[1..] {
+            if si == 0 {
+                return false;
+            }
+            if fi % 2 == 1 || fi == si {
+                fr = Self::node_hash(c, &fr);
+                sr = Self::node_hash(c, &sr);
+                while fi % 2 == 0 && fi != 0 {
+                    fi >>= 1;
+                    si >>= 1;
+                }
+            } else {
+                sr = Self::node_hash(&sr, c);
+            }
+            fi >>= 1;
+            si >>= 1;
+        }
+
+        si == 0 && fr == old_root && sr == new_root
+    }
+
+    fn check_size(&self, size: usize) -> Result<()> {
+        if size > self.len() {
+            return Err(HashError::ValueError(format!(
+                "tree size {} exceeds the number of leaves ({})",
+                size,
+                self.len()
+            )));
+        }
+        Ok(())
+    }
+
+    // MTH(D[a:b])
+    fn subtree_root(&self, a: usize, b: usize) -> Vec<u8> {
+        let n = b - a;
+        if n == 0 {
+            return Self::empty_root();
+        }
+        // complete subtrees are stored as they are
+        if n.is_power_of_two() && a.is_multiple_of(n) {
+            let l = n.trailing_zeros() as usize;
+            return self.levels[l][a >> l].clone();
+        }
+
+        let k = Self::split(n);
+        Self::node_hash(&self.subtree_root(a, a + k), &self.subtree_root(a + k, b))
+    }
+
+    // PATH(m, D[a:b])
+    fn path(&self, m: usize, a: usize, b: usize, res: &mut Vec<Vec<u8>>) {
+        if b - a == 1 {
+            return;
+        }
+
+        let k = Self::split(b - a);
+        if m < a + k {
+            self.path(m, a, a + k, res);
+            res.push(self.subtree_root(a + k, b));
+        } else {
+            self.path(m, a + k, b, res);
+            res.push(self.subtree_root(a, a + k));
+        }
+    }
+
+    // SUBPROOF(m, D[a:b], complete)
+    fn subproof(&self, m: usize, a: usize, b: usize, complete: bool, res: &mut Vec<Vec<u8>>) {
+        if m == b - a {
+            if !complete {
+                res.push(self.subtree_root(a, b));
+            }
+            return;
+        }
+
+        let k = Self::split(b - a);
+        if m <= k {
+            self.subproof(m, a, a + k, complete, res);
+            res.push(self.subtree_root(a + k, b));
+        } else {
+            self.subproof(m - k, a + k, b, false, res);
+            res.push(self.subtree_root(a, a + k));
+        }
+    }
+
+    // the largest power of two smaller than n (n > 1)
+    fn split(n: usize) -> usize {
+        1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        digest::HashAlgorithm,
+        sha256::Sha256,
+        util::{from_hex, to_hex},
+    };
+
+    use super::MerkleTree;
+
+    // test leaves of the certificate-transparency reference implementation
+    fn leaves() -> Vec<Vec<u8>> {
+        [
+            "",
+            "00",
+            "10",
+            "2021",
+            "3031",
+            "40414243",
+            "5051525354555657",
+            "606162636465666768696a6b6c6d6e6f",
+        ]
+        .into_iter()
+        .map(|x| from_hex(x).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn merkle_root() {
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+
+        let mut tree = MerkleTree::<Sha256>::new();
+        assert_eq!(
+            to_hex(&tree.root()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        for (i, (leaf, root)) in leaves().iter().zip(roots).enumerate() {
+            assert_eq!(tree.push(leaf), i);
+            assert_eq!(to_hex(&tree.root()), root);
+        }
+
+        let tree = MerkleTree::<Sha256>::from_leaves(leaves());
+        for (i, root) in roots.iter().enumerate() {
+            assert_eq!(to_hex(&tree.root_at(i + 1).unwrap()), *root);
+        }
+        assert!(tree.root_at(9).is_err());
+    }
+
+    #[test]
+    fn merkle_inclusion() {
+        let tree = MerkleTree::<Sha256>::from_leaves(leaves());
+
+        let proof = tree.inclusion_proof(0, 8).unwrap();
+        assert_eq!(
+            proof.iter().map(|x| to_hex(x)).collect::<Vec<_>>(),
+            [
+                "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+            ]
+        );
+        let proof = tree.inclusion_proof(5, 8).unwrap();
+        assert_eq!(
+            proof.iter().map(|x| to_hex(x)).collect::<Vec<_>>(),
+            [
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ]
+        );
+
+        for size in 1..=8 {
+            let root = tree.root_at(size).unwrap();
+            for (i, leaf) in leaves().iter().enumerate().take(size) {
+                let proof = tree.inclusion_proof(i, size).unwrap();
+                assert!(MerkleTree::<Sha256>::verify_inclusion(
+                    leaf, i, size, &proof, &root
+                ));
+                assert!(!MerkleTree::<Sha256>::verify_inclusion(
+                    b"x", i, size, &proof, &root
+                ));
+                if !proof.is_empty() {
+                    assert!(!MerkleTree::<Sha256>::verify_inclusion(
+                        leaf,
+                        i,
+                        size,
+                        &proof[1..],
+                        &root
+                    ));
+                }
+            }
+        }
+
+        assert!(tree.inclusion_proof(8, 8).is_err());
+        assert!(tree.inclusion_proof(0, 9).is_err());
+    }
+
+    #[test]
+    fn merkle_consistency() {
+        let tree = MerkleTree::<Sha256>::from_leaves(leaves());
+
+        let proof = tree.consistency_proof(6, 8).unwrap();
+        assert_eq!(
+            proof.iter().map(|x| to_hex(x)).collect::<Vec<_>>(),
+            [
+                "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ]
+        );
+        assert!(tree.consistency_proof(4, 8).unwrap().len() == 1);
+        assert!(tree.consistency_proof(8, 8).unwrap().is_empty());
+
+        for new_size in 1..=8 {
+            let new_root = tree.root_at(new_size).unwrap();
+            for old_size in 1..=new_size {
+                let old_root = tree.root_at(old_size).unwrap();
+                let proof = tree.consistency_proof(old_size, new_size).unwrap();
+                assert!(MerkleTree::<Sha256>::verify_consistency(
+                    old_size, new_size, &old_root, &new_root, &proof
+                ));
+
+                let fake = Sha256::digest(b"fake");
+                assert!(!MerkleTree::<Sha256>::verify_consistency(
+                    old_size, new_size, &fake, &new_root, &proof
+                ));
+                if old_size < new_size {
+                    assert!(!MerkleTree::<Sha256>::verify_consistency(
+                        old_size, new_size, &old_root, &fake, &proof
+                    ));
+                }
+            }
+        }
+
+        assert!(tree.consistency_proof(0, 8).is_err());
+        assert!(tree.consistency_proof(5, 4).is_err());
+        assert!(tree.consistency_proof(4, 9).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use std::thread;
+
+use crate::{
+    cshake::{left_encode, right_encode, CShake128, CShake256},
+    digest::XofReader,
+    shake::{KeccakReader, Shake128, Shake256},
+    util::available_threads,
+};
+
+// at least this many blocks are split across threads
+const PARALLEL_MIN_BLOCKS: usize = 64;
+
+macro_rules! parallelhash {
+    ($name:ident, $cshake:ident, $shake:ident, $cv_size:expr) => {
+        // The message is cut into blocks of `block_size` bytes which are hashed independently
+        // (on several threads for long messages) and the results are hashed together.
+        #[derive(Clone)]
+        pub struct $name {
+            inner: $cshake,
+            block_size: usize,
+            buffer: Vec<u8>,
+            blocks: usize,
+            threads: usize,
+        }
+
+        impl $name {
+            // bytes
+            const CV_SIZE: usize = $cv_size;
+
+            pub fn new(block_size: usize, customization: &[u8]) -> Self {
+                assert!(block_size > 0, "block size must be positive");
+
+                let mut inner = $cshake::new(b"ParallelHash", customization);
+                inner.update(&left_encode(block_size));
+
+                Self {
+                    inner,
+                    block_size,
+                    buffer: vec![],
+                    blocks: 0,
+                    threads: available_threads(),
+                }
+            }
+
+            pub fn set_threads(&mut self, threads: usize) {
+                self.threads = threads.max(1);
+            }
+
+            fn block_cv(block: &[u8]) -> Vec<u8> {
+                $shake::hash(block.to_vec(), Self::CV_SIZE)
+            }
+
+            fn absorb_blocks(&mut self, m: &[u8]) {
+                let n = m.len() / self.block_size;
+                let cvs: Vec<Vec<u8>> = if self.threads > 1 && n >= PARALLEL_MIN_BLOCKS {
+                    let per_thread = n.div_ceil(self.threads) * self.block_size;
+                    thread::scope(|s| {
+                        let handles: Vec<_> = m
+                            .chunks(per_thread)
+                            .map(|x| {
+                                s.spawn(|| {
+                                    x.chunks(self.block_size)
+                                        .map(Self::block_cv)
+                                        .collect::<Vec<_>>()
+                                })
+                            })
+                            .collect();
+                        handles
+                            .into_iter()
+                            .flat_map(|x| x.join().unwrap())
+                            .collect()
+                    })
+                } else {
+                    m.chunks(self.block_size).map(Self::block_cv).collect()
+                };
+
+                for cv in &cvs {
+                    self.inner.update(cv);
+                }
+                self.blocks += cvs.len();
+            }
+
+            pub fn update(&mut self, m: &[u8]) {
+                let mut m = m;
+                if !self.buffer.is_empty() {
+                    let n = (self.block_size - self.buffer.len()).min(m.len());
+                    self.buffer.extend_from_slice(&m[..n]);
+                    m = &m[n..];
+                    if self.buffer.len() < self.block_size {
+                        return;
+                    }
+                    let block = std::mem::take(&mut self.buffer);
+                    self.absorb_blocks(&block);
+                }
+
+                let full = m.len() - m.len() % self.block_size;
+                self.absorb_blocks(&m[..full]);
+                self.buffer.extend_from_slice(&m[full..]);
+            }
+
+            fn finish(&mut self, len_bits: usize) {
+                if !self.buffer.is_empty() {
+                    let block = std::mem::take(&mut self.buffer);
+                    self.absorb_blocks(&block);
+                }
+                self.inner.update(&right_encode(self.blocks));
+                self.inner.update(&right_encode(len_bits));
+            }
+
+            pub fn finalize(mut self, len: usize) -> Vec<u8> {
+                self.finish(len * 8);
+                self.inner.finalize_xof().read(len)
+            }
+
+            // ParallelHashXOF
+            pub fn finalize_xof(mut self) -> KeccakReader {
+                self.finish(0);
+                self.inner.finalize_xof()
+            }
+
+            pub fn hash(m: &[u8], block_size: usize, len: usize, customization: &[u8]) -> Vec<u8> {
+                let mut hasher = Self::new(block_size, customization);
+                hasher.update(m);
+                hasher.finalize(len)
+            }
+        }
+    };
+}
+
+// ParallelHash128 and ParallelHash256 (SP 800-185 section 6)
+parallelhash!(ParallelHash128, CShake128, Shake128, 32);
+parallelhash!(ParallelHash256, CShake256, Shake256, 64);
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::XofReader, util::to_hex};
+
+    use super::{ParallelHash128, ParallelHash256};
+
+    fn message() -> Vec<u8> {
+        [0x00..0x08, 0x10..0x18, 0x20..0x28]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn parallelhash() {
+        // SP 800-185 samples
+        let m = message();
+        assert_eq!(
+            to_hex(&ParallelHash128::hash(&m, 8, 32, b"")),
+            "ba8dc1d1d979331d3f813603c67f72609ab5e44b94a0b8f9af46514454a2b4f5"
+        );
+        assert_eq!(
+            to_hex(&ParallelHash128::hash(&m, 8, 32, b"Parallel Data")),
+            "fc484dcb3f84dceedc353438151bee58157d6efed0445a81f165e495795b7206"
+        );
+        assert_eq!(
+            to_hex(&ParallelHash256::hash(&m, 8, 64, b"")),
+            "bc1ef124da34495e948ead207dd9842235da432d2bbc54b4c110e64c451105531b7f2a3e0ce055c02805e7c2de1fb746af97a1dd01f43b824e31b87612410429"
+        );
+
+        let mut hasher = ParallelHash128::new(8, b"");
+        hasher.update(&m);
+        assert_eq!(
+            to_hex(&hasher.finalize_xof().read(32)),
+            "fe47d661e49ffe5b7d999922c062356750caf552985b8e8ce6667f2727c3c8d3"
+        );
+    }
+
+    #[test]
+    fn parallelhash_streaming() {
+        let m: Vec<u8> = (0..100000).map(|i| (i % 251) as u8).collect();
+        let md = ParallelHash256::hash(&m, 1000, 64, b"abc");
+        assert_eq!(
+            to_hex(&md),
+            "3e1c63844db74ea99ed2cda8a3d43890bb12299bd12f6aa39add3e55f96cc13f550b74b6c385ebacede7956f0a3fc59583e2bf256ee1ad5294f046d817daad6f"
+        );
+
+        for chunk_size in [1, 999, 1000, 1001, 30000] {
+            let mut hasher = ParallelHash256::new(1000, b"abc");
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(64), md);
+        }
+
+        let mut hasher = ParallelHash256::new(1000, b"abc");
+        hasher.set_threads(1);
+        hasher.update(&m);
+        assert_eq!(hasher.finalize(64), md);
+    }
+}
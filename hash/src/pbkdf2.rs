@@ -0,0 +1,161 @@
+use std::marker::PhantomData;
+
+use crate::{digest::HashAlgorithm, error::HashError, hmac::Hmac};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// PBKDF2 with HMAC as the PRF (RFC 8018 section 5.2)
+pub struct Pbkdf2<H: HashAlgorithm> {
+    _hash: PhantomData<H>,
+}
+
+impl<H: HashAlgorithm> Pbkdf2<H> {
+    // (2^32 - 1) * hLen, which does not fit usize on 32-bit targets
+    pub const MAX_OUTPUT_SIZE: usize = 0xffffffff_usize.saturating_mul(H::OUTPUT_SIZE);
+
+    pub fn derive(
+        password: &[u8],
+        salt: &[u8],
+        iterations: usize,
+        dk_len: usize,
+    ) -> Result<Vec<u8>> {
+        if iterations == 0 {
+            return Err(HashError::ValueError(
+                "iteration count must be positive".into(),
+            ));
+        }
+
+        if dk_len > Self::MAX_OUTPUT_SIZE {
+            return Err(HashError::ValueError("derived key too long".into()));
+        }
+
+        // the keyed HMAC state is computed once and cloned for every PRF call
+        let prf = Hmac::<H>::new(password);
+
+        let mut dk = Vec::with_capacity(dk_len + H::OUTPUT_SIZE);
+        for i in 1..=dk_len.div_ceil(H::OUTPUT_SIZE) {
+            let mut hmac = prf.clone();
+            hmac.update(salt);
+            hmac.update(&(i as u32).to_be_bytes());
+            let mut u = hmac.finalize();
+
+            let mut t = u.clone();
+            for _ in 1..iterations {
+                let mut hmac = prf.clone();
+                hmac.update(u.as_ref());
+                u = hmac.finalize();
+                for (x, y) in t.as_mut().iter_mut().zip(u.as_ref()) {
+                    *x ^= y;
+                }
+            }
+            dk.extend_from_slice(t.as_ref());
+        }
+        dk.truncate(dk_len);
+
+        Ok(dk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sha1::Sha1, sha256::Sha256, sha512::Sha512, util::to_hex};
+
+    use super::Pbkdf2;
+
+    #[test]
+    fn pbkdf2_sha1() {
+        // RFC 6070 (https://www.rfc-editor.org/rfc/rfc6070)
+        let tests = [
+            (
+                b"password".to_vec(),
+                b"salt".to_vec(),
+                1,
+                20,
+                "0c60c80f961f0e71f3a9b524af6012062fe037a6",
+            ),
+            (
+                b"password".to_vec(),
+                b"salt".to_vec(),
+                2,
+                20,
+                "ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957",
+            ),
+            (
+                b"password".to_vec(),
+                b"salt".to_vec(),
+                4096,
+                20,
+                "4b007901b765489abead49d926f721d065a429c1",
+            ),
+            (
+                b"passwordPASSWORDpassword".to_vec(),
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt".to_vec(),
+                4096,
+                25,
+                "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038",
+            ),
+            (
+                b"pass\0word".to_vec(),
+                b"sa\0lt".to_vec(),
+                4096,
+                16,
+                "56fa6aa75548099dcc37d7f03425e0c3",
+            ),
+        ];
+
+        for (p, s, c, dk_len, dk) in tests {
+            assert_eq!(
+                to_hex(&Pbkdf2::<Sha1>::derive(&p, &s, c, dk_len).unwrap()),
+                dk
+            );
+        }
+    }
+
+    #[test]
+    fn pbkdf2_sha2() {
+        let tests = [
+            (
+                b"password".to_vec(),
+                b"salt".to_vec(),
+                1,
+                20,
+                "120fb6cffcf8b32c43e7225256c4f837a86548c9",
+            ),
+            (
+                b"password".to_vec(),
+                b"salt".to_vec(),
+                4096,
+                20,
+                "c5e478d59288c841aa530db6845c4c8d962893a0",
+            ),
+            (
+                b"passwordPASSWORDpassword".to_vec(),
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt".to_vec(),
+                4096,
+                25,
+                "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c",
+            ),
+        ];
+        for (p, s, c, dk_len, dk) in tests {
+            assert_eq!(
+                to_hex(&Pbkdf2::<Sha256>::derive(&p, &s, c, dk_len).unwrap()),
+                dk
+            );
+        }
+
+        assert_eq!(
+            to_hex(&Pbkdf2::<Sha256>::derive(b"password", b"salt", 1, 64).unwrap()),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b4dbf3a2f3dad3377264bb7b8e8330d4efc7451418617dabef683735361cdc18c"
+        );
+        assert_eq!(
+            to_hex(&Pbkdf2::<Sha512>::derive(b"password", b"salt", 4096, 20).unwrap()),
+            "d197b1b33db0143e018b12f3d1d1479e6cdebdcc"
+        );
+        assert_eq!(
+            to_hex(&Pbkdf2::<Sha512>::derive(b"password", b"salt", 1, 64).unwrap()),
+            "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"
+        );
+
+        assert!(Pbkdf2::<Sha256>::derive(b"password", b"salt", 0, 32).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+// RIPEMD-160 (https://homes.esat.kuleuven.be/~bosselae/ripemd160.html)
+// Kept for interoperability (e.g. Bitcoin addresses); prefer SHA-2/SHA-3 for new designs.
+
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    sha256::Sha256,
+    util::words_to_bytes,
+};
+
+pub struct Ripemd160Digest {
+    data: <Ripemd160Digest as HashDigest>::Digest,
+}
+
+impl Ripemd160Digest {
+    fn new(data: <Ripemd160Digest as HashDigest>::Digest) -> Self {
+        Self { data }
+    }
+}
+
+impl HashDigest for Ripemd160Digest {
+    type Digest = [u32; 5];
+    type Bytes = [u8; 20];
+
+    fn digest(&self) -> Self::Digest {
+        self.data
+    }
+
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_le_bytes)
+    }
+
+    fn hexdigest(&self) -> String {
+        let mut res = String::new();
+        for i in self.digest_u8() {
+            res.push_str(&format!("{:0>2x?}", i));
+        }
+        res
+    }
+}
+
+#[derive(Clone)]
+pub struct Ripemd160 {
+    hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl Default for Ripemd160 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ripemd160 {
+    // bytes
+    const BLOCK_SIZE: usize = 64;
+    const WORD_SIZE: usize = 4;
+    pub const DIGEST_SIZE: usize = 20;
+    const IV: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE] =
+        [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    const K: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+    const KK: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+    // message word selection of the left and right lines
+    const R: [usize; 80] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9,
+        5, 2, 14, 11, 8, 3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, 1, 9, 11, 10, 0, 8,
+        12, 4, 13, 3, 7, 15, 14, 5, 6, 2, 4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+    ];
+    const RR: [usize; 80] = [
+        5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, 6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8,
+        12, 4, 9, 1, 2, 15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, 8, 6, 4, 1, 3, 11,
+        15, 0, 5, 12, 2, 13, 9, 7, 10, 14, 12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+    ];
+    // rotation amounts of the left and right lines
+    const S: [u32; 80] = [
+        11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, 7, 6, 8, 13, 11, 9, 7, 15, 7, 12,
+        15, 9, 11, 7, 13, 12, 11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, 11, 12, 14,
+        15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, 9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11,
+        8, 5, 6,
+    ];
+    const SS: [u32; 80] = [
+        8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, 9, 13, 15, 7, 12, 8, 9, 11, 7, 7,
+        12, 7, 6, 15, 13, 11, 9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, 15, 5, 8, 11,
+        14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8, 8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13,
+        11, 11,
+    ];
+
+    // f_j of the left line; the right line uses f_(79 - j)
+    fn f(j: usize, x: u32, y: u32, z: u32) -> u32 {
+        match j / 16 {
+            0 => x ^ y ^ z,
+            1 => (x & y) | (!x & z),
+            2 => (x | !y) ^ z,
+            3 => (x & z) | (y & !z),
+            _ => x ^ (y | !z),
+        }
+    }
+
+    // `m` is the unprocessed tail of a message whose total length is `l` bytes
+    fn pad(m: Vec<u8>, l: usize) -> Vec<u8> {
+        let mods = (l + 1) % Self::BLOCK_SIZE;
+        let k = if mods > 56 {
+            Self::BLOCK_SIZE - (mods - 56)
+        } else {
+            56 - mods
+        };
+        let mut res = m;
+        res.push(0b10000000);
+        res.extend(vec![0x0_u8; k]);
+        res.extend_from_slice(&((l as u64).wrapping_mul(8)).to_le_bytes());
+        res
+    }
+
+    fn compress(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        let mut x = [0u32; 16];
+        for (w, b) in x.iter_mut().zip(block.chunks_exact(Self::WORD_SIZE)) {
+            *w = u32::from_le_bytes(b.try_into().unwrap());
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = *hs;
+        let [mut aa, mut bb, mut cc, mut dd, mut ee] = *hs;
+        for j in 0..80 {
+            let t = a
+                .wrapping_add(Self::f(j, b, c, d))
+                .wrapping_add(x[Self::R[j]])
+                .wrapping_add(Self::K[j / 16])
+                .rotate_left(Self::S[j])
+                .wrapping_add(e);
+            a = e;
+            e = d;
+            d = c.rotate_left(10);
+            c = b;
+            b = t;
+
+            let t = aa
+                .wrapping_add(Self::f(79 - j, bb, cc, dd))
+                .wrapping_add(x[Self::RR[j]])
+                .wrapping_add(Self::KK[j / 16])
+                .rotate_left(Self::SS[j])
+                .wrapping_add(ee);
+            aa = ee;
+            ee = dd;
+            dd = cc.rotate_left(10);
+            cc = bb;
+            bb = t;
+        }
+
+        let t = hs[1].wrapping_add(c).wrapping_add(dd);
+        hs[1] = hs[2].wrapping_add(d).wrapping_add(ee);
+        hs[2] = hs[3].wrapping_add(e).wrapping_add(aa);
+        hs[3] = hs[4].wrapping_add(a).wrapping_add(bb);
+        hs[4] = hs[0].wrapping_add(b).wrapping_add(cc);
+        hs[0] = t;
+    }
+
+    pub fn new() -> Self {
+        Self {
+            hs: Self::IV,
+            buffer: Vec::with_capacity(Self::BLOCK_SIZE),
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.len += m.len();
+
+        let mut m = m;
+        if !self.buffer.is_empty() {
+            let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+            self.buffer.extend_from_slice(&m[..n]);
+            m = &m[n..];
+            if self.buffer.len() < Self::BLOCK_SIZE {
+                return;
+            }
+            Self::compress(&mut self.hs, self.buffer.as_slice().try_into().unwrap());
+            self.buffer.clear();
+        }
+
+        let mut blocks = m.chunks_exact(Self::BLOCK_SIZE);
+        for block in &mut blocks {
+            Self::compress(&mut self.hs, block.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalize(self) -> Ripemd160Digest {
+        let mut hs = self.hs;
+        for block in Self::pad(self.buffer, self.len).chunks_exact(Self::BLOCK_SIZE) {
+            Self::compress(&mut hs, block.try_into().unwrap());
+        }
+
+        Ripemd160Digest::new(hs)
+    }
+
+    pub fn hash(m: Vec<u8>) -> Result<Ripemd160Digest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+
+    // RIPEMD160(SHA256(m)), as used for Bitcoin addresses
+    pub fn hash160(m: Vec<u8>) -> Result<Ripemd160Digest, Vec<u8>> {
+        Self::hash(Sha256::hash(m)?.digest_u8())
+    }
+}
+
+impl HashAlgorithm for Ripemd160 {
+    type Output = Ripemd160Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Ripemd160::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Ripemd160::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Ripemd160::finalize(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashDigest, ripemd160::Ripemd160, util::from_hex};
+
+    #[test]
+    fn ripemd160() {
+        // test vectors from the RIPEMD-160 page
+        let cases = [
+            ("", "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+            ("abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+            ("message digest", "5d0689ef49d2fae572b881b123a85ffa21595f36"),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "9b752e45573d4b39f4dbd3323cab82bf63326bfb",
+            ),
+        ];
+        for (m, md) in cases {
+            assert_eq!(
+                Ripemd160::hash(m.as_bytes().to_vec()).unwrap().hexdigest(),
+                md
+            );
+        }
+    }
+
+    #[test]
+    fn ripemd160_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        for chunk_size in [1, 7, 63, 64, 65, 300] {
+            let mut hasher = Ripemd160::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finalize().hexdigest(),
+                "6864b0b9f86a879be2680824c81dbce9c5350281"
+            );
+        }
+    }
+
+    #[test]
+    fn hash160() {
+        // public key of the "Technical background of version 1 Bitcoin addresses" example
+        let pk =
+            from_hex("0250863ad64a87ae8a2fe83c1af1a8403cb53f53e486d8511dad8a04887e5b2352").unwrap();
+        assert_eq!(
+            Ripemd160::hash160(pk).unwrap().hexdigest(),
+            "f54a5851e9372b87810a8e60cdd2e7cfd80b6e31"
+        );
+    }
+}
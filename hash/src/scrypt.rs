@@ -0,0 +1,224 @@
+use std::thread;
+
+use crate::{error::HashError, pbkdf2::Pbkdf2, sha256::Sha256, util::available_threads};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+// scrypt (RFC 7914)
+#[derive(Clone)]
+pub struct Scrypt {
+    // CPU/memory cost, a power of 2
+    n: usize,
+    // block size factor
+    r: usize,
+    // parallelization factor
+    p: usize,
+    threads: usize,
+}
+
+impl Scrypt {
+    // bytes
+    const BLOCK_SIZE: usize = 64;
+
+    // The memory used by each ROMix is 128 * r * n bytes. The p ROMix calls run on up to
+    // p threads.
+    pub fn new(n: usize, r: usize, p: usize) -> Result<Self> {
+        if n < 2 || !n.is_power_of_two() {
+            return Err(HashError::ValueError(
+                "N must be a power of 2 greater than 1".into(),
+            ));
+        }
+        if r == 0 || p == 0 {
+            return Err(HashError::ValueError("r and p must be positive".into()));
+        }
+        if r as u64 * p as u64 >= 1 << 30 {
+            return Err(HashError::ValueError("r * p must be less than 2^30".into()));
+        }
+        // N < 2^(128 * r / 8)
+        if 16 * r < usize::BITS as usize && n >> (16 * r) != 0 {
+            return Err(HashError::ValueError(
+                "N must be less than 2^(16 * r)".into(),
+            ));
+        }
+        if (2 * Self::BLOCK_SIZE * r).checked_mul(n.max(p)).is_none() {
+            return Err(HashError::ValueError("parameters too large".into()));
+        }
+
+        Ok(Self {
+            n,
+            r,
+            p,
+            threads: available_threads().min(p),
+        })
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.clamp(1, self.p);
+    }
+
+    // Salsa20/8 core (RFC 7914 section 3)
+    fn salsa20_8(b: &mut [u32; 16]) {
+        let mut x = *b;
+        let quarter = |x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize| {
+            x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+            x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+            x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+            x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+        };
+        for _ in 0..4 {
+            // columns
+            quarter(&mut x, 0, 4, 8, 12);
+            quarter(&mut x, 5, 9, 13, 1);
+            quarter(&mut x, 10, 14, 2, 6);
+            quarter(&mut x, 15, 3, 7, 11);
+            // rows
+            quarter(&mut x, 0, 1, 2, 3);
+            quarter(&mut x, 5, 6, 7, 4);
+            quarter(&mut x, 10, 11, 8, 9);
+            quarter(&mut x, 15, 12, 13, 14);
+        }
+        for (b, x) in b.iter_mut().zip(x) {
+            *b = b.wrapping_add(x);
+        }
+    }
+
+    // scryptBlockMix (RFC 7914 section 4) on 2r 64-byte blocks of words
+    fn block_mix(b: &[u32], out: &mut [u32]) {
+        let r = b.len() / 32;
+        let mut x: [u32; 16] = b[b.len() - 16..].try_into().unwrap();
+        for (i, block) in b.chunks_exact(16).enumerate() {
+            for (x, y) in x.iter_mut().zip(block) {
+                *x ^= y;
+            }
+            Self::salsa20_8(&mut x);
+            // even blocks go to the first half, odd ones to the second
+            let j = i / 2 + (i % 2) * r;
+            out[16 * j..16 * (j + 1)].copy_from_slice(&x);
+        }
+    }
+
+    // scryptROMix (RFC 7914 section 5)
+    fn ro_mix(&self, block: &mut [u8]) {
+        let len = block.len() / 4;
+        let mut x: Vec<u32> = block
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+            .collect();
+        let mut y = vec![0; len];
+
+        let mut v = vec![0; len * self.n];
+        for i in 0..self.n {
+            v[len * i..len * (i + 1)].copy_from_slice(&x);
+            Self::block_mix(&x, &mut y);
+            std::mem::swap(&mut x, &mut y);
+        }
+        for _ in 0..self.n {
+            // Integerify: the first 8 bytes of the last 64-byte block, modulo N
+            let k = x[len - 16] as u64 | (x[len - 15] as u64) << 32;
+            let j = (k & (self.n as u64 - 1)) as usize;
+            for (x, v) in x.iter_mut().zip(&v[len * j..len * (j + 1)]) {
+                *x ^= v;
+            }
+            Self::block_mix(&x, &mut y);
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        for (b, x) in block.chunks_exact_mut(4).zip(x) {
+            b.copy_from_slice(&x.to_le_bytes());
+        }
+    }
+
+    pub fn derive(&self, password: &[u8], salt: &[u8], dk_len: usize) -> Result<Vec<u8>> {
+        let block_len = 2 * Self::BLOCK_SIZE * self.r;
+        let mut b = Pbkdf2::<Sha256>::derive(password, salt, 1, block_len * self.p)?;
+
+        if self.threads > 1 {
+            let per_thread = self.p.div_ceil(self.threads) * block_len;
+            thread::scope(|s| {
+                for chunk in b.chunks_mut(per_thread) {
+                    s.spawn(|| chunk.chunks_mut(block_len).for_each(|x| self.ro_mix(x)));
+                }
+            });
+        } else {
+            b.chunks_mut(block_len).for_each(|x| self.ro_mix(x));
+        }
+
+        Pbkdf2::<Sha256>::derive(password, &b, 1, dk_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::{from_hex, to_hex};
+
+    use super::Scrypt;
+
+    #[test]
+    fn scrypt_core() {
+        // RFC 7914 sections 8 and 9
+        let b = from_hex(
+            "7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1dee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e",
+        )
+        .unwrap();
+        let mut x: [u32; 16] =
+            std::array::from_fn(|i| u32::from_le_bytes(b[4 * i..4 * (i + 1)].try_into().unwrap()));
+        Scrypt::salsa20_8(&mut x);
+        let x: Vec<u8> = x.iter().flat_map(|x| x.to_le_bytes()).collect();
+        assert_eq!(
+            to_hex(&x),
+            "a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81"
+        );
+
+        let mut b = from_hex(
+            "f7ce0b653d2d72a4108cf5abe912ffdd777616dbbb27a70e8204f3ae2d0f6fad89f68f4811d1e87bcc3bd7400a9ffd29094f0184639574f39ae5a1315217bcd7894991447213bb226c25b54da86370fbcd984380374666bb8ffcb5bf40c254b067d27c51ce4ad5fed829c90b505a571b7f4d1cad6a523cda770e67bceaaf7e89",
+        )
+        .unwrap();
+        Scrypt::new(16, 1, 1).unwrap().ro_mix(&mut b);
+        assert_eq!(
+            to_hex(&b),
+            "79ccc193629debca047f0b70604bf6b62ce3dd4a9626e355fafc6198e6ea2b46d58413673b99b029d665c357601fb426a0b2f4bba200ee9f0a43d19b571a9c71ef1142e65d5a266fddca832ce59faa7cac0b9cf1be2bffca300d01ee387619c4ae12fd4438f203a0e4e1c47ec314861f4e9087cb33396a6873e8f9d2539a4b8e"
+        );
+    }
+
+    #[test]
+    fn scrypt() {
+        // RFC 7914 section 12
+        assert_eq!(
+            to_hex(&Scrypt::new(16, 1, 1).unwrap().derive(b"", b"", 64).unwrap()),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+
+        let dk = "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640";
+        for threads in [1, 3] {
+            let mut scrypt = Scrypt::new(1024, 8, 16).unwrap();
+            scrypt.set_threads(threads);
+            assert_eq!(
+                to_hex(&scrypt.derive(b"password", b"NaCl", 64).unwrap()),
+                dk
+            );
+        }
+
+        assert_eq!(
+            to_hex(
+                &Scrypt::new(16384, 8, 1)
+                    .unwrap()
+                    .derive(b"pleaseletmein", b"SodiumChloride", 64)
+                    .unwrap()
+            ),
+            "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"
+        );
+    }
+
+    #[test]
+    fn scrypt_params() {
+        assert!(Scrypt::new(0, 1, 1).is_err());
+        assert!(Scrypt::new(1, 1, 1).is_err());
+        assert!(Scrypt::new(1000, 1, 1).is_err());
+        assert!(Scrypt::new(16, 0, 1).is_err());
+        assert!(Scrypt::new(16, 1, 0).is_err());
+        assert!(Scrypt::new(16, 1 << 15, 1 << 15).is_err());
+        // N < 2^16 for r = 1
+        assert!(Scrypt::new(1 << 16, 1, 1).is_err());
+        assert!(Scrypt::new(1 << 15, 1, 1).is_ok());
+    }
+}
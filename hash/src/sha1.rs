@@ -1,6 +1,11 @@
 use std::ops::Shl;
 
-use crate::digest::HashDigest;
+use crate::{
+    accel::{self, Backend},
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    util::{decode_state, encode_state, words_to_bytes},
+};
 
 pub struct Sha1Digest {
     data: <Sha1Digest as HashDigest>::Digest,
@@ -10,8 +15,17 @@ impl Sha1Digest {
     fn new(data: <Sha1Digest as HashDigest>::Digest) -> Self {
         Self { data }
     }
+}
+
+impl HashDigest for Sha1Digest {
+    type Digest = [u32; 5];
+    type Bytes = [u8; 20];
+
+    fn digest(&self) -> Self::Digest {
+        self.data
+    }
 
-    pub fn digest_u8(&self) -> Vec<u8> {
+    fn digest_u8(&self) -> Vec<u8> {
         self.digest()
             .map(|x| {
                 [
@@ -25,13 +39,9 @@ impl Sha1Digest {
             .flatten()
             .collect()
     }
-}
-
-impl HashDigest for Sha1Digest {
-    type Digest = [u32; 5];
 
-    fn digest(&self) -> Self::Digest {
-        self.data
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_be_bytes)
     }
 
     fn hexdigest(&self) -> String {
@@ -42,7 +52,18 @@ impl HashDigest for Sha1Digest {
     }
 }
 
-pub struct Sha1 {}
+#[derive(Clone)]
+pub struct Sha1 {
+    hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha1 {
     // bytes
@@ -76,9 +97,10 @@ impl Sha1 {
         }
     }
 
-    fn pad(m: Vec<u8>) -> Vec<u8> {
-        let l = m.len();
-        let mods = (l + 1) % Self::BLOCK_SIZE;
+    // `m` is the unprocessed tail of a message whose total length is `l` bits.
+    // If `l` is not a multiple of 8, the last `l % 8` bits are the high bits of the last byte of `m`.
+    pub(crate) fn pad(m: Vec<u8>, l: usize) -> Vec<u8> {
+        let mods = (l / 8 + 1) % Self::BLOCK_SIZE;
         let k = if mods > 56 {
             Self::BLOCK_SIZE - (mods - 56)
         } else if mods < 56 {
@@ -88,10 +110,15 @@ impl Sha1 {
         };
         let res = {
             let mut res = m;
-            res.push(0b10000000);
+            if l.is_multiple_of(8) {
+                res.push(0b10000000);
+            } else {
+                let last = res.last_mut().unwrap();
+                *last = (*last & (0xff << (8 - l % 8))) | (0b10000000 >> (l % 8));
+            }
             res.extend(vec![0x0 as u8; k]);
             for i in 1..9 {
-                res.push((((l * 8) & ((0xff) << (8 * (8 - i)))) >> (64 - 8 * i)) as u8);
+                res.push(((l & ((0xff) << (8 * (8 - i)))) >> (64 - 8 * i)) as u8);
             }
             res
         };
@@ -109,77 +136,253 @@ impl Sha1 {
         Ok(res)
     }
 
-    pub fn hash(m: Vec<u8>) -> Result<Sha1Digest, Vec<u8>> {
-        let m = Self::parse(Self::pad(m)).unwrap();
-
-        let mut hs = Self::IV;
-        for block in m {
-            let mut a = hs[0];
-            let mut b = hs[1];
-            let mut c = hs[2];
-            let mut d = hs[3];
-            let mut e = hs[4];
-            let wts: [u32; 80] = {
-                let mut wts = [0; 80];
-                for t in 0..80 {
-                    if t < 16 {
-                        wts[t] = (block[4 * t] as u32).shl(24)
-                            | (block[4 * t + 1] as u32).shl(16)
-                            | (block[4 * t + 2] as u32).shl(8)
-                            | (block[4 * t + 3] as u32);
-                    } else {
-                        wts[t] = (wts[t - 3] ^ wts[t - 8] ^ wts[t - 14] ^ wts[t - 16] as u32)
-                            .rotate_left(1);
-                    };
-                }
-                wts
+    fn compress(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        match accel::backend() {
+            #[cfg(target_arch = "x86_64")]
+            Backend::ShaNi => unsafe { accel::x86::sha1_shani(hs, block) },
+            _ => Self::compress_portable(hs, block),
+        }
+    }
+
+    pub(crate) fn compress_portable(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        Self::rounds(hs, &Self::schedule(block));
+    }
+
+    #[inline(always)]
+    fn schedule(block: &[u8; Self::BLOCK_SIZE]) -> [u32; 80] {
+        let mut wts = [0; 80];
+        for t in 0..80 {
+            if t < 16 {
+                wts[t] = (block[4 * t] as u32).shl(24)
+                    | (block[4 * t + 1] as u32).shl(16)
+                    | (block[4 * t + 2] as u32).shl(8)
+                    | (block[4 * t + 3] as u32);
+            } else {
+                wts[t] =
+                    (wts[t - 3] ^ wts[t - 8] ^ wts[t - 14] ^ wts[t - 16] as u32).rotate_left(1);
             };
-            for t in 0..80 {
-                let tt = a
-                    .rotate_left(5)
-                    .wrapping_add(Self::ft(b, c, d, t))
-                    .wrapping_add(e)
-                    .wrapping_add(Self::kt(t))
-                    .wrapping_add(wts[t]);
-
-                e = d;
-                d = c;
-                c = b.rotate_left(30);
-                b = a;
-                a = tt;
+        }
+        wts
+    }
+
+    #[inline(always)]
+    fn rounds(hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE], wts: &[u32; 80]) {
+        let mut a = hs[0];
+        let mut b = hs[1];
+        let mut c = hs[2];
+        let mut d = hs[3];
+        let mut e = hs[4];
+        for t in 0..80 {
+            let tt = a
+                .rotate_left(5)
+                .wrapping_add(Self::ft(b, c, d, t))
+                .wrapping_add(e)
+                .wrapping_add(Self::kt(t))
+                .wrapping_add(wts[t]);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = tt;
+        }
+        hs[0] = hs[0].wrapping_add(a);
+        hs[1] = hs[1].wrapping_add(b);
+        hs[2] = hs[2].wrapping_add(c);
+        hs[3] = hs[3].wrapping_add(d);
+        hs[4] = hs[4].wrapping_add(e);
+    }
+
+    pub fn new() -> Self {
+        Self::from_state(Self::IV, 0)
+    }
+
+    // resumes from the chaining value `hs` after `len` bytes (a multiple of the block size) were processed
+    pub(crate) fn from_state(hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE], len: usize) -> Self {
+        Self {
+            hs,
+            buffer: Vec::with_capacity(Self::BLOCK_SIZE),
+            len,
+        }
+    }
+
+    // serializes the chaining value, the number of bytes hashed so far and the buffered
+    // partial block, so that hashing can be resumed later with `import_state`
+    pub fn export_state(&self) -> Vec<u8> {
+        let hs: Vec<u8> = self.hs.iter().flat_map(|x| x.to_be_bytes()).collect();
+        encode_state("SHA-1", &hs, self.len, &self.buffer)
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        let (hs, len, buffer) = decode_state("SHA-1", state, Self::DIGEST_SIZE, Self::BLOCK_SIZE)?;
+
+        let mut res = Self::from_state(
+            std::array::from_fn(|i| {
+                u32::from_be_bytes(
+                    hs[Self::WORD_SIZE * i..Self::WORD_SIZE * (i + 1)]
+                        .try_into()
+                        .unwrap(),
+                )
+            }),
+            len,
+        );
+        res.buffer.extend_from_slice(buffer);
+        Ok(res)
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.len += m.len();
+
+        let mut m = m;
+        if !self.buffer.is_empty() {
+            let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+            self.buffer.extend_from_slice(&m[..n]);
+            m = &m[n..];
+            if self.buffer.len() < Self::BLOCK_SIZE {
+                return;
             }
-            hs[0] = hs[0].wrapping_add(a);
-            hs[1] = hs[1].wrapping_add(b);
-            hs[2] = hs[2].wrapping_add(c);
-            hs[3] = hs[3].wrapping_add(d);
-            hs[4] = hs[4].wrapping_add(e);
+            Self::compress(&mut self.hs, self.buffer.as_slice().try_into().unwrap());
+            self.buffer.clear();
+        }
+
+        let mut blocks = m.chunks_exact(Self::BLOCK_SIZE);
+        for block in &mut blocks {
+            Self::compress(&mut self.hs, block.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalize(self) -> Sha1Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha1Digest {
+        assert!(bits < 8, "bits must be less than 8");
+
+        let mut buffer = self.buffer;
+        if bits > 0 {
+            buffer.push(last);
+        }
+
+        let mut hs = self.hs;
+        for block in Self::parse(Self::pad(buffer, self.len * 8 + bits)).unwrap() {
+            Self::compress(&mut hs, &block);
         }
 
-        Ok(Sha1Digest::new(hs))
+        Sha1Digest::new(hs)
+    }
+
+    pub fn hash(m: Vec<u8>) -> Result<Sha1Digest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha1Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl HashAlgorithm for Sha1 {
+    type Output = Sha1Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha1::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha1::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha1::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha1::finalize_bits(self, last, bits))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{digest::HashDigest, sha1::Sha1};
-    use cavp_tester::cavp_test::CavpTest;
+    use crate::{cavp::check_vectors, digest::HashDigest, sha1::Sha1, sha256::Sha256};
 
-    #[tokio::test]
-    async fn sha1() {
+    #[test]
+    fn sha1() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha1_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha1::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha1::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
+        check_vectors::<Sha1>("SHA1", &["ShortMsg", "LongMsg", "Monte"]);
+    }
+
+    #[test]
+    fn sha1_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha1::hash(m.clone()).unwrap().hexdigest();
 
-            assert!(t.test(md.trim().to_string()).is_ok());
+        for chunk_size in [1, 7, 63, 64, 65, 300] {
+            let mut hasher = Sha1::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
     }
+
+    #[test]
+    fn sha1_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha1::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha1::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha1::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+
+        let mut hasher = Sha1::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha256::import_state(&state).is_err());
+        assert!(Sha1::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha1::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha1_bits() {
+        assert_eq!(
+            Sha1::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "29826b003b906e660eff4027ce98af3531ac75ba"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha1::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "a0631bcc33b4d7383d724ccb470bc0d20e93a4ed"
+        );
+        assert_eq!(
+            Sha1::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha1::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha1::hash_bits(m, 1009).is_err());
+    }
 }
@@ -1,4 +1,9 @@
-use crate::{digest::HashDigest, sha256::Sha256};
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    sha256::Sha256,
+    util::words_to_bytes,
+};
 
 pub struct Sha224Digest {
     data: <Sha224Digest as HashDigest>::Digest,
@@ -12,11 +17,20 @@ impl Sha224Digest {
 
 impl HashDigest for Sha224Digest {
     type Digest = [u32; 7];
+    type Bytes = [u8; 28];
 
     fn digest(&self) -> Self::Digest {
         self.data
     }
 
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_be_bytes)
+    }
+
     fn hexdigest(&self) -> String {
         let mut res = String::new();
         for i in self.data {
@@ -26,7 +40,16 @@ impl HashDigest for Sha224Digest {
     }
 }
 
-pub struct Sha224 {}
+#[derive(Clone)]
+pub struct Sha224 {
+    inner: Sha256,
+}
+
+impl Default for Sha224 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha224 {
     // bytes
@@ -38,35 +61,141 @@ impl Sha224 {
         0xbefa4fa4,
     ];
 
+    pub fn new() -> Self {
+        Self {
+            inner: Sha256::with_iv(Self::IV),
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.inner.update(m);
+    }
+
+    pub fn export_state(&self) -> Vec<u8> {
+        self.inner.export_state_as("SHA-224")
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        Ok(Self {
+            inner: Sha256::import_state_as("SHA-224", state)?,
+        })
+    }
+
+    pub fn finalize(self) -> Sha224Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha224Digest {
+        let sha256 = self.inner.finalize_bits(last, bits);
+
+        Sha224Digest::new(sha256.digest()[0..7].try_into().unwrap())
+    }
+
     pub fn hash(m: Vec<u8>) -> Result<Sha224Digest, Vec<u8>> {
-        let sha256 = Sha256::hash_iv(m, Self::IV)?;
+        let mut hasher = Self::new();
+        hasher.update(&m);
 
-        Ok(Sha224Digest::new(sha256.digest()[0..7].try_into().unwrap()))
+        Ok(hasher.finalize())
+    }
+
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha224Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl HashAlgorithm for Sha224 {
+    type Output = Sha224Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha224::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha224::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha224::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha224::finalize_bits(self, last, bits))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use cavp_tester::cavp_test::CavpTest;
+    use crate::{cavp::check_vectors, digest::HashDigest, sha224::Sha224, sha256::Sha256};
 
-    use crate::{digest::HashDigest, sha224::Sha224};
-
-    #[tokio::test]
-    async fn sha224() {
+    #[test]
+    fn sha224() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha224_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha224::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha224::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
-
-            assert!(t.test(md.trim().to_string()).is_ok());
+        check_vectors::<Sha224>("SHA224", &["ShortMsg", "LongMsg", "Monte"]);
+    }
+
+    #[test]
+    fn sha224_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha224::hash(m.clone()).unwrap().hexdigest();
+
+        for chunk_size in [1, 7, 63, 64, 65, 300] {
+            let mut hasher = Sha224::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha224_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha224::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha224::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha224::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
+
+        let mut hasher = Sha224::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha256::import_state(&state).is_err());
+        assert!(Sha224::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha224::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha224_bits() {
+        assert_eq!(
+            Sha224::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "6d4d77c75ba1db37aec07bc5c3dbdbaaf6ec654c6f546ebfcda4b60c"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha224::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "3c59e5d68e9c899e6918120417d2bede21e17fed743d83fe35a5604e"
+        );
+        assert_eq!(
+            Sha224::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha224::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha224::hash_bits(m, 1009).is_err());
     }
 }
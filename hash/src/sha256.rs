@@ -1,24 +1,38 @@
 use std::ops::Shl;
 
-use crate::digest::HashDigest;
+use crate::{
+    accel::{self, Backend},
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    util::{decode_state, encode_state, words_to_bytes},
+};
 
 pub struct Sha256Digest {
     data: <Sha256Digest as HashDigest>::Digest,
 }
 
 impl Sha256Digest {
-    fn new(data: <Sha256Digest as HashDigest>::Digest) -> Self {
+    pub(crate) fn new(data: <Sha256Digest as HashDigest>::Digest) -> Self {
         Self { data }
     }
 }
 
 impl HashDigest for Sha256Digest {
     type Digest = [u32; 8];
+    type Bytes = [u8; 32];
 
     fn digest(&self) -> Self::Digest {
         self.data
     }
 
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_be_bytes)
+    }
+
     fn hexdigest(&self) -> String {
         let mut res = String::new();
         for i in self.data {
@@ -28,18 +42,29 @@ impl HashDigest for Sha256Digest {
     }
 }
 
-pub struct Sha256 {}
+#[derive(Clone)]
+pub struct Sha256 {
+    hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha256 {
     // bytes
     const BLOCK_SIZE: usize = 64;
     const WORD_SIZE: usize = 4;
     pub const DIGEST_SIZE: usize = 32;
-    const IV: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE] = [
+    pub(crate) const IV: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE] = [
         0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
         0x5be0cd19,
     ];
-    const K: [u32; 64] = [
+    pub(crate) const K: [u32; 64] = [
         0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
         0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
         0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
@@ -53,45 +78,46 @@ impl Sha256 {
     ];
 
     #[inline(always)]
-    fn kt(t: usize) -> u32 {
+    pub(crate) fn kt(t: usize) -> u32 {
         Self::K[t]
     }
 
     #[allow(non_snake_case)]
     #[inline(always)]
-    fn S0(x: u32) -> u32 {
+    pub(crate) fn S0(x: u32) -> u32 {
         x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
     }
 
     #[allow(non_snake_case)]
     #[inline(always)]
-    fn S1(x: u32) -> u32 {
+    pub(crate) fn S1(x: u32) -> u32 {
         x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
     }
 
     #[inline(always)]
-    fn s0(x: u32) -> u32 {
+    pub(crate) fn s0(x: u32) -> u32 {
         x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)
     }
 
     #[inline(always)]
-    fn s1(x: u32) -> u32 {
+    pub(crate) fn s1(x: u32) -> u32 {
         x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
     }
 
     #[inline(always)]
-    fn ch(x: u32, y: u32, z: u32) -> u32 {
+    pub(crate) fn ch(x: u32, y: u32, z: u32) -> u32 {
         (x & y) ^ ((!x) & z)
     }
 
     #[inline(always)]
-    fn maj(x: u32, y: u32, z: u32) -> u32 {
+    pub(crate) fn maj(x: u32, y: u32, z: u32) -> u32 {
         (x & y) ^ (x & z) ^ (y & z)
     }
 
-    fn pad(m: Vec<u8>) -> Vec<u8> {
-        let l = m.len();
-        let mods = (l + 1) % Self::BLOCK_SIZE;
+    // `m` is the unprocessed tail of a message whose total length is `l` bits.
+    // If `l` is not a multiple of 8, the last `l % 8` bits are the high bits of the last byte of `m`.
+    pub(crate) fn pad(m: Vec<u8>, l: usize) -> Vec<u8> {
+        let mods = (l / 8 + 1) % Self::BLOCK_SIZE;
         let k = if mods > 56 {
             Self::BLOCK_SIZE - (mods - 56)
         } else if mods < 56 {
@@ -101,10 +127,15 @@ impl Sha256 {
         };
         let res = {
             let mut res = m;
-            res.push(0b10000000);
+            if l.is_multiple_of(8) {
+                res.push(0b10000000);
+            } else {
+                let last = res.last_mut().unwrap();
+                *last = (*last & (0xff << (8 - l % 8))) | (0b10000000 >> (l % 8));
+            }
             res.extend(vec![0x0 as u8; k]);
             for i in 1..9 {
-                res.push((((l * 8) & ((0xff) << (8 * (8 - i)))) >> (64 - 8 * i)) as u8);
+                res.push(((l & ((0xff) << (8 * (8 - i)))) >> (64 - 8 * i)) as u8);
             }
             res
         };
@@ -122,86 +153,273 @@ impl Sha256 {
         Ok(res)
     }
 
+    fn compress(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        match accel::backend() {
+            #[cfg(target_arch = "x86_64")]
+            Backend::ShaNi => unsafe { accel::x86::sha256_shani(hs, block) },
+            _ => Self::compress_portable(hs, block),
+        }
+    }
+
+    pub(crate) fn compress_portable(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        Self::rounds(hs, &Self::schedule(block));
+    }
+
+    #[inline(always)]
+    pub(crate) fn schedule(block: &[u8; Self::BLOCK_SIZE]) -> [u32; 64] {
+        let mut wts = [0; 64];
+        for t in 0..64 {
+            if t < 16 {
+                wts[t] = (block[4 * t] as u32).shl(24)
+                    | (block[4 * t + 1] as u32).shl(16)
+                    | (block[4 * t + 2] as u32).shl(8)
+                    | (block[4 * t + 3] as u32);
+            } else {
+                wts[t] = Self::s1(wts[t - 2])
+                    .wrapping_add(wts[t - 7])
+                    .wrapping_add(Self::s0(wts[t - 15]).wrapping_add(wts[t - 16]));
+            };
+        }
+        wts
+    }
+
+    #[inline(always)]
+    fn rounds(hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE], wts: &[u32; 64]) {
+        let mut tmps = *hs;
+        for t in 0..64 {
+            let t1 = tmps[7]
+                .wrapping_add(Self::S1(tmps[4]))
+                .wrapping_add(Self::ch(tmps[4], tmps[5], tmps[6]))
+                .wrapping_add(Self::kt(t))
+                .wrapping_add(wts[t]);
+            let t2 = Self::S0(tmps[0]).wrapping_add(Self::maj(tmps[0], tmps[1], tmps[2]));
+
+            tmps[7] = tmps[6];
+            tmps[6] = tmps[5];
+            tmps[5] = tmps[4];
+            tmps[4] = tmps[3].wrapping_add(t1);
+            tmps[3] = tmps[2];
+            tmps[2] = tmps[1];
+            tmps[1] = tmps[0];
+            tmps[0] = t1.wrapping_add(t2);
+        }
+        for i in 0..hs.len() {
+            hs[i] = hs[i].wrapping_add(tmps[i]);
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::with_iv(Self::IV)
+    }
+
+    pub fn with_iv(iv: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE]) -> Self {
+        Self::from_state(iv, 0)
+    }
+
+    // resumes from the chaining value `hs` after `len` bytes (a multiple of the block size) were processed
+    pub(crate) fn from_state(hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE], len: usize) -> Self {
+        Self {
+            hs,
+            buffer: Vec::with_capacity(Self::BLOCK_SIZE),
+            len,
+        }
+    }
+
+    // serializes the chaining value, the number of bytes hashed so far and the buffered
+    // partial block, so that hashing can be resumed later with `import_state`
+    pub fn export_state(&self) -> Vec<u8> {
+        self.export_state_as("SHA-256")
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        Self::import_state_as("SHA-256", state)
+    }
+
+    // also used by SHA-224 under its own label
+    pub(crate) fn export_state_as(&self, label: &str) -> Vec<u8> {
+        let hs: Vec<u8> = self.hs.iter().flat_map(|x| x.to_be_bytes()).collect();
+        encode_state(label, &hs, self.len, &self.buffer)
+    }
+
+    pub(crate) fn import_state_as(label: &str, state: &[u8]) -> Result<Self, HashError> {
+        let (hs, len, buffer) = decode_state(label, state, Self::DIGEST_SIZE, Self::BLOCK_SIZE)?;
+
+        let mut res = Self::from_state(
+            std::array::from_fn(|i| {
+                u32::from_be_bytes(
+                    hs[Self::WORD_SIZE * i..Self::WORD_SIZE * (i + 1)]
+                        .try_into()
+                        .unwrap(),
+                )
+            }),
+            len,
+        );
+        res.buffer.extend_from_slice(buffer);
+        Ok(res)
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.len += m.len();
+
+        let mut m = m;
+        if !self.buffer.is_empty() {
+            let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+            self.buffer.extend_from_slice(&m[..n]);
+            m = &m[n..];
+            if self.buffer.len() < Self::BLOCK_SIZE {
+                return;
+            }
+            Self::compress(&mut self.hs, self.buffer.as_slice().try_into().unwrap());
+            self.buffer.clear();
+        }
+
+        let mut blocks = m.chunks_exact(Self::BLOCK_SIZE);
+        for block in &mut blocks {
+            Self::compress(&mut self.hs, block.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalize(self) -> Sha256Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha256Digest {
+        assert!(bits < 8, "bits must be less than 8");
+
+        let mut buffer = self.buffer;
+        if bits > 0 {
+            buffer.push(last);
+        }
+
+        let mut hs = self.hs;
+        for block in Self::parse(Self::pad(buffer, self.len * 8 + bits)).unwrap() {
+            Self::compress(&mut hs, &block);
+        }
+
+        Sha256Digest::new(hs)
+    }
+
     pub fn hash(m: Vec<u8>) -> Result<Sha256Digest, Vec<u8>> {
         Self::hash_iv(m, Self::IV)
     }
 
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha256Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+
     pub fn hash_iv(
         m: Vec<u8>,
         iv: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
     ) -> Result<Sha256Digest, Vec<u8>> {
-        let m = Self::parse(Self::pad(m)).unwrap();
-
-        let mut hs = iv;
-        for block in m {
-            let mut tmps = hs;
-            let wts: [u32; 64] = {
-                let mut wts = [0; 64];
-                for t in 0..64 {
-                    if t < 16 {
-                        wts[t] = (block[4 * t] as u32).shl(24)
-                            | (block[4 * t + 1] as u32).shl(16)
-                            | (block[4 * t + 2] as u32).shl(8)
-                            | (block[4 * t + 3] as u32);
-                    } else {
-                        wts[t] = Self::s1(wts[t - 2])
-                            .wrapping_add(wts[t - 7])
-                            .wrapping_add(Self::s0(wts[t - 15]).wrapping_add(wts[t - 16]));
-                    };
-                }
-                wts
-            };
+        let mut hasher = Self::with_iv(iv);
+        hasher.update(&m);
 
-            for t in 0..64 {
-                let t1 = tmps[7]
-                    .wrapping_add(Self::S1(tmps[4]))
-                    .wrapping_add(Self::ch(tmps[4], tmps[5], tmps[6]))
-                    .wrapping_add(Self::kt(t))
-                    .wrapping_add(wts[t]);
-                let t2 = Self::S0(tmps[0]).wrapping_add(Self::maj(tmps[0], tmps[1], tmps[2]));
-
-                tmps[7] = tmps[6];
-                tmps[6] = tmps[5];
-                tmps[5] = tmps[4];
-                tmps[4] = tmps[3].wrapping_add(t1);
-                tmps[3] = tmps[2];
-                tmps[2] = tmps[1];
-                tmps[1] = tmps[0];
-                tmps[0] = t1.wrapping_add(t2);
-            }
-            for i in 0..hs.len() {
-                hs[i] = hs[i].wrapping_add(tmps[i]);
-            }
-        }
+        Ok(hasher.finalize())
+    }
+}
+
+impl HashAlgorithm for Sha256 {
+    type Output = Sha256Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha256::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha256::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha256::finalize(self)
+    }
 
-        Ok(Sha256Digest::new(hs))
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha256::finalize_bits(self, last, bits))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use cavp_tester::cavp_test::CavpTest;
-
-    use crate::digest::HashDigest;
+    use crate::{cavp::check_vectors, digest::HashDigest, sha224::Sha224};
 
     use super::Sha256;
 
-    #[tokio::test]
-    async fn sha256() {
+    #[test]
+    fn sha256() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha256_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha256::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha256::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
+        check_vectors::<Sha256>("SHA256", &["ShortMsg", "LongMsg", "Monte"]);
+    }
+
+    #[test]
+    fn sha256_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha256::hash(m.clone()).unwrap().hexdigest();
 
-            assert!(t.test(md.trim().to_string()).is_ok());
+        for chunk_size in [1, 7, 63, 64, 65, 300] {
+            let mut hasher = Sha256::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
     }
+
+    #[test]
+    fn sha256_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha256::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha256::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha256::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha224::import_state(&state).is_err());
+        assert!(Sha256::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha256::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha256_bits() {
+        assert_eq!(
+            Sha256::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "8f136783ea6f000dccc4295d4db99b648f1c8f483b27248db103ba7cd567dbba"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha256::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "da781fe629f59414e766b52268bc99980ad2cf87f2f03e8256ee3bf3bd52c317"
+        );
+        assert_eq!(
+            Sha256::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha256::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha256::hash_bits(m, 1009).is_err());
+    }
 }
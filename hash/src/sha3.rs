@@ -0,0 +1,198 @@
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    keccak::Sponge,
+};
+
+macro_rules! keccak_hash {
+    ($name:ident, $digest:ident, $size:expr, $suffix:expr) => {
+        pub struct $digest {
+            data: <$digest as HashDigest>::Digest,
+        }
+
+        impl $digest {
+            fn new(data: <$digest as HashDigest>::Digest) -> Self {
+                Self { data }
+            }
+        }
+
+        impl HashDigest for $digest {
+            type Digest = [u8; $size];
+            type Bytes = [u8; $size];
+
+            fn digest(&self) -> Self::Digest {
+                self.data
+            }
+
+            fn digest_u8(&self) -> Vec<u8> {
+                self.data.to_vec()
+            }
+
+            fn into_bytes(self) -> Self::Bytes {
+                self.data
+            }
+
+            fn hexdigest(&self) -> String {
+                let mut res = String::new();
+                for i in self.data {
+                    res.push_str(&format!("{:0>2x?}", i));
+                }
+                res
+            }
+        }
+
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl $name {
+            // bytes
+            const BLOCK_SIZE: usize = 200 - 2 * $size;
+            pub const DIGEST_SIZE: usize = $size;
+
+            pub fn new() -> Self {
+                Self {
+                    sponge: Sponge::new(Self::BLOCK_SIZE, $suffix),
+                }
+            }
+
+            pub fn update(&mut self, m: &[u8]) {
+                self.sponge.absorb(m);
+            }
+
+            pub fn finalize(self) -> $digest {
+                let mut sponge = self.sponge;
+                let mut data = [0; $size];
+                sponge.squeeze(&mut data);
+
+                $digest::new(data)
+            }
+
+            pub fn hash(m: Vec<u8>) -> Result<$digest, Vec<u8>> {
+                let mut hasher = Self::new();
+                hasher.update(&m);
+
+                Ok(hasher.finalize())
+            }
+        }
+
+        impl HashAlgorithm for $name {
+            type Output = $digest;
+            const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+            const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+            fn new() -> Self {
+                $name::new()
+            }
+
+            fn update(&mut self, m: &[u8]) {
+                $name::update(self, m);
+            }
+
+            fn finalize(self) -> Self::Output {
+                $name::finalize(self)
+            }
+        }
+    };
+}
+
+// SHA-3 (FIPS 202)
+keccak_hash!(Sha3_224, Sha3_224Digest, 28, 0x06);
+keccak_hash!(Sha3_256, Sha3_256Digest, 32, 0x06);
+keccak_hash!(Sha3_384, Sha3_384Digest, 48, 0x06);
+keccak_hash!(Sha3_512, Sha3_512Digest, 64, 0x06);
+
+// Keccak-256 with the original Keccak padding (as used by Ethereum)
+keccak_hash!(Keccak256, Keccak256Digest, 32, 0x01);
+
+#[cfg(test)]
+mod tests {
+    use crate::digest::HashDigest;
+
+    use super::{Keccak256, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+
+    // NIST example values (https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values)
+    fn messages() -> [Vec<u8>; 3] {
+        [vec![], b"abc".to_vec(), vec![0xa3; 200]]
+    }
+
+    #[test]
+    fn sha3_224() {
+        let mds = [
+            "6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7",
+            "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf",
+            "9376816aba503f72f96ce7eb65ac095deee3be4bf9bbc2a1cb7e11e0",
+        ];
+        for (m, md) in messages().into_iter().zip(mds) {
+            assert_eq!(Sha3_224::hash(m).unwrap().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha3_256() {
+        let mds = [
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            "79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787",
+        ];
+        for (m, md) in messages().into_iter().zip(mds) {
+            assert_eq!(Sha3_256::hash(m).unwrap().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha3_384() {
+        let mds = [
+            "0c63a75b845e4f7d01107d852e4c2485c51a50aaaa94fc61995e71bbee983a2ac3713831264adb47fb6bd1e058d5f004",
+            "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25",
+            "1881de2ca7e41ef95dc4732b8f5f002b189cc1e42b74168ed1732649ce1dbcdd76197a31fd55ee989f2d7050dd473e8f",
+        ];
+        for (m, md) in messages().into_iter().zip(mds) {
+            assert_eq!(Sha3_384::hash(m).unwrap().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha3_512() {
+        let mds = [
+            "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26",
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            "e76dfad22084a8b1467fcf2ffa58361bec7628edf5f3fdc0e4805dc48caeeca81b7c13c30adf52a3659584739a2df46be589c51ca1a4a8416df6545a1ce8ba00",
+        ];
+        for (m, md) in messages().into_iter().zip(mds) {
+            assert_eq!(Sha3_512::hash(m).unwrap().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn keccak256() {
+        assert_eq!(
+            Keccak256::hash(vec![]).unwrap().hexdigest(),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            Keccak256::hash(b"abc".to_vec()).unwrap().hexdigest(),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn sha3_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha3_256::hash(m.clone()).unwrap().hexdigest();
+
+        for chunk_size in [1, 7, 135, 136, 137, 300] {
+            let mut hasher = Sha3_256::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{digest::HashDigest, sha512::Sha512};
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    sha512::Sha512,
+    util::words_to_bytes,
+};
 
 pub struct Sha384Digest {
     data: <Sha384Digest as HashDigest>::Digest,
@@ -12,11 +17,20 @@ impl Sha384Digest {
 
 impl HashDigest for Sha384Digest {
     type Digest = [u64; 6];
+    type Bytes = [u8; 48];
 
     fn digest(&self) -> Self::Digest {
         self.data
     }
 
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u64::to_be_bytes)
+    }
+
     fn hexdigest(&self) -> String {
         let mut res = String::new();
         for i in self.data {
@@ -26,7 +40,16 @@ impl HashDigest for Sha384Digest {
     }
 }
 
-pub struct Sha384 {}
+#[derive(Clone)]
+pub struct Sha384 {
+    inner: Sha512,
+}
+
+impl Default for Sha384 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha384 {
     // bytes
@@ -44,34 +67,141 @@ impl Sha384 {
         0x47b5481dbefa4fa4,
     ];
 
+    pub fn new() -> Self {
+        Self {
+            inner: Sha512::with_iv(Self::IV),
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.inner.update(m);
+    }
+
+    pub fn export_state(&self) -> Vec<u8> {
+        self.inner.export_state_as("SHA-384")
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        Ok(Self {
+            inner: Sha512::import_state_as("SHA-384", state)?,
+        })
+    }
+
+    pub fn finalize(self) -> Sha384Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha384Digest {
+        let sha512 = self.inner.finalize_bits(last, bits);
+
+        Sha384Digest::new(sha512.digest()[0..6].try_into().unwrap())
+    }
+
     pub fn hash(m: Vec<u8>) -> Result<Sha384Digest, Vec<u8>> {
-        let sha512 = Sha512::hash_iv(m, Self::IV)?;
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
 
-        Ok(Sha384Digest::new(sha512.digest()[0..6].try_into().unwrap()))
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha384Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl HashAlgorithm for Sha384 {
+    type Output = Sha384Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha384::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha384::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha384::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha384::finalize_bits(self, last, bits))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{digest::HashDigest, sha384::Sha384};
-    use cavp_tester::cavp_test::CavpTest;
+    use crate::{cavp::check_vectors, digest::HashDigest, sha384::Sha384, sha512::Sha512};
 
-    #[tokio::test]
-    async fn sha384() {
+    #[test]
+    fn sha384() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha384_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha384::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha384::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
-
-            assert!(t.test(md.trim().to_string()).is_ok());
+        check_vectors::<Sha384>("SHA384", &["ShortMsg", "LongMsg", "Monte"]);
+    }
+
+    #[test]
+    fn sha384_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha384::hash(m.clone()).unwrap().hexdigest();
+
+        for chunk_size in [1, 7, 127, 128, 129, 300] {
+            let mut hasher = Sha384::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha384_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha384::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha384::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha384::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
+
+        let mut hasher = Sha384::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha512::import_state(&state).is_err());
+        assert!(Sha384::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha384::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha384_bits() {
+        assert_eq!(
+            Sha384::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "bf00f5cd44a68bc4e5c8ede07022b893143703e06842c4d57c47d8f6a6114d52682dee829d32873849f4a327bcd8a6dd"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha384::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "e5d0bd9376034037536e73e4c1c4dc03546b23543ef102f263ef5b1b05142ae3687e607352b1df627568b0bc161938ac"
+        );
+        assert_eq!(
+            Sha384::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha384::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha384::hash_bits(m, 1009).is_err());
     }
 }
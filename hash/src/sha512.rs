@@ -1,24 +1,38 @@
 use std::ops::Shl;
 
-use crate::digest::HashDigest;
+use crate::{
+    accel::{self, Backend},
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    util::{decode_state, encode_state, words_to_bytes},
+};
 
 pub struct Sha512Digest {
     data: <Sha512Digest as HashDigest>::Digest,
 }
 
 impl Sha512Digest {
-    fn new(data: <Sha512Digest as HashDigest>::Digest) -> Self {
+    pub(crate) fn new(data: <Sha512Digest as HashDigest>::Digest) -> Self {
         Self { data }
     }
 }
 
 impl HashDigest for Sha512Digest {
     type Digest = [u64; 8];
+    type Bytes = [u8; 64];
 
     fn digest(&self) -> Self::Digest {
         self.data
     }
 
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u64::to_be_bytes)
+    }
+
     fn hexdigest(&self) -> String {
         let mut res = String::new();
         for i in self.data {
@@ -28,14 +42,25 @@ impl HashDigest for Sha512Digest {
     }
 }
 
-pub struct Sha512 {}
+#[derive(Clone)]
+pub struct Sha512 {
+    hs: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl Default for Sha512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha512 {
     // bytes
     const BLOCK_SIZE: usize = 128;
     const WORD_SIZE: usize = 8;
     pub const DIGEST_SIZE: usize = 64;
-    const IV: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE] = [
+    pub(crate) const IV: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE] = [
         0x6a09e667f3bcc908,
         0xbb67ae8584caa73b,
         0x3c6ef372fe94f82b,
@@ -46,7 +71,7 @@ impl Sha512 {
         0x5be0cd19137e2179,
     ];
 
-    const K: [u64; 80] = [
+    pub(crate) const K: [u64; 80] = [
         0x428a2f98d728ae22,
         0x7137449123ef65cd,
         0xb5c0fbcfec4d3b2f,
@@ -130,45 +155,46 @@ impl Sha512 {
     ];
 
     #[inline(always)]
-    fn kt(t: usize) -> u64 {
+    pub(crate) fn kt(t: usize) -> u64 {
         Self::K[t]
     }
 
     #[inline(always)]
-    fn ch(x: u64, y: u64, z: u64) -> u64 {
+    pub(crate) fn ch(x: u64, y: u64, z: u64) -> u64 {
         (x & y) ^ ((!x) & z)
     }
 
     #[inline(always)]
-    fn maj(x: u64, y: u64, z: u64) -> u64 {
+    pub(crate) fn maj(x: u64, y: u64, z: u64) -> u64 {
         (x & y) ^ (x & z) ^ (y & z)
     }
 
     #[allow(non_snake_case)]
     #[inline(always)]
-    fn S0(x: u64) -> u64 {
+    pub(crate) fn S0(x: u64) -> u64 {
         x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39)
     }
 
     #[allow(non_snake_case)]
     #[inline(always)]
-    fn S1(x: u64) -> u64 {
+    pub(crate) fn S1(x: u64) -> u64 {
         x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41)
     }
 
     #[inline(always)]
-    fn s0(x: u64) -> u64 {
+    pub(crate) fn s0(x: u64) -> u64 {
         x.rotate_right(1) ^ x.rotate_right(8) ^ (x >> 7)
     }
 
     #[inline(always)]
-    fn s1(x: u64) -> u64 {
+    pub(crate) fn s1(x: u64) -> u64 {
         x.rotate_right(19) ^ x.rotate_right(61) ^ (x >> 6)
     }
 
-    fn pad(m: Vec<u8>) -> Vec<u8> {
-        let l = m.len();
-        let mods = (l + 1) % Self::BLOCK_SIZE;
+    // `m` is the unprocessed tail of a message whose total length is `l` bits.
+    // If `l` is not a multiple of 8, the last `l % 8` bits are the high bits of the last byte of `m`.
+    pub(crate) fn pad(m: Vec<u8>, l: usize) -> Vec<u8> {
+        let mods = (l / 8 + 1) % Self::BLOCK_SIZE;
         let k = if mods > 112 {
             Self::BLOCK_SIZE - (mods - 112)
         } else if mods < 112 {
@@ -178,11 +204,16 @@ impl Sha512 {
         };
         let res = {
             let mut res = m;
+            if l.is_multiple_of(8) {
+                res.push(0b10000000);
+            } else {
+                let last = res.last_mut().unwrap();
+                *last = (*last & (0xff << (8 - l % 8))) | (0b10000000 >> (l % 8));
+            }
             let l: u128 = l as u128;
-            res.push(0b10000000);
             res.extend(vec![0x0 as u8; k]);
             for i in 1..=16 {
-                res.push((((l * 8) & ((0xff) << (8 * (16 - i)))) >> (128 - 8 * i)) as u8);
+                res.push(((l & ((0xff) << (8 * (16 - i)))) >> (128 - 8 * i)) as u8);
             }
             res
         };
@@ -200,90 +231,271 @@ impl Sha512 {
         Ok(res)
     }
 
-    pub fn hash_iv(
-        m: Vec<u8>,
-        iv: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE],
-    ) -> Result<Sha512Digest, Vec<u8>> {
-        let m = Self::parse(Self::pad(m)).unwrap();
-
-        let mut hs = iv;
-        for block in m {
-            let mut tmps = hs;
-            let wts: [u64; 80] = {
-                let mut wts = [0; 80];
-                for t in 0..80 {
-                    if t < 16 {
-                        wts[t] = (block[8 * t] as u64).shl(56)
-                            | (block[8 * t + 1] as u64).shl(48)
-                            | (block[8 * t + 2] as u64).shl(40)
-                            | (block[8 * t + 3] as u64).shl(32)
-                            | (block[8 * t + 4] as u64).shl(24)
-                            | (block[8 * t + 5] as u64).shl(16)
-                            | (block[8 * t + 6] as u64).shl(8)
-                            | (block[8 * t + 7] as u64);
-                    } else {
-                        wts[t] = Self::s1(wts[t - 2])
-                            .wrapping_add(wts[t - 7])
-                            .wrapping_add(Self::s0(wts[t - 15]).wrapping_add(wts[t - 16]));
-                    };
-                }
-                wts
-            };
+    fn compress(
+        hs: &mut [u64; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        match accel::sha512_backend() {
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => unsafe { accel::x86::sha512_avx2(hs, block) },
+            _ => Self::compress_portable(hs, block),
+        }
+    }
 
+    pub(crate) fn compress_portable(
+        hs: &mut [u64; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        let mut tmps = *hs;
+        let wts: [u64; 80] = {
+            let mut wts = [0; 80];
             for t in 0..80 {
-                let t1 = tmps[7]
-                    .wrapping_add(Self::S1(tmps[4]))
-                    .wrapping_add(Self::ch(tmps[4], tmps[5], tmps[6]))
-                    .wrapping_add(Self::kt(t))
-                    .wrapping_add(wts[t]);
-                let t2 = Self::S0(tmps[0]).wrapping_add(Self::maj(tmps[0], tmps[1], tmps[2]));
-
-                tmps[7] = tmps[6];
-                tmps[6] = tmps[5];
-                tmps[5] = tmps[4];
-                tmps[4] = tmps[3].wrapping_add(t1);
-                tmps[3] = tmps[2];
-                tmps[2] = tmps[1];
-                tmps[1] = tmps[0];
-                tmps[0] = t1.wrapping_add(t2);
+                if t < 16 {
+                    wts[t] = (block[8 * t] as u64).shl(56)
+                        | (block[8 * t + 1] as u64).shl(48)
+                        | (block[8 * t + 2] as u64).shl(40)
+                        | (block[8 * t + 3] as u64).shl(32)
+                        | (block[8 * t + 4] as u64).shl(24)
+                        | (block[8 * t + 5] as u64).shl(16)
+                        | (block[8 * t + 6] as u64).shl(8)
+                        | (block[8 * t + 7] as u64);
+                } else {
+                    wts[t] = Self::s1(wts[t - 2])
+                        .wrapping_add(wts[t - 7])
+                        .wrapping_add(Self::s0(wts[t - 15]).wrapping_add(wts[t - 16]));
+                };
             }
-            for i in 0..hs.len() {
-                hs[i] = hs[i].wrapping_add(tmps[i]);
+            wts
+        };
+
+        for t in 0..80 {
+            let t1 = tmps[7]
+                .wrapping_add(Self::S1(tmps[4]))
+                .wrapping_add(Self::ch(tmps[4], tmps[5], tmps[6]))
+                .wrapping_add(Self::kt(t))
+                .wrapping_add(wts[t]);
+            let t2 = Self::S0(tmps[0]).wrapping_add(Self::maj(tmps[0], tmps[1], tmps[2]));
+
+            tmps[7] = tmps[6];
+            tmps[6] = tmps[5];
+            tmps[5] = tmps[4];
+            tmps[4] = tmps[3].wrapping_add(t1);
+            tmps[3] = tmps[2];
+            tmps[2] = tmps[1];
+            tmps[1] = tmps[0];
+            tmps[0] = t1.wrapping_add(t2);
+        }
+        for i in 0..hs.len() {
+            hs[i] = hs[i].wrapping_add(tmps[i]);
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::with_iv(Self::IV)
+    }
+
+    pub fn with_iv(iv: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE]) -> Self {
+        Self::from_state(iv, 0)
+    }
+
+    // resumes from the chaining value `hs` after `len` bytes (a multiple of the block size) were processed
+    pub(crate) fn from_state(hs: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE], len: usize) -> Self {
+        Self {
+            hs,
+            buffer: Vec::with_capacity(Self::BLOCK_SIZE),
+            len,
+        }
+    }
+
+    // serializes the chaining value, the number of bytes hashed so far and the buffered
+    // partial block, so that hashing can be resumed later with `import_state`
+    pub fn export_state(&self) -> Vec<u8> {
+        self.export_state_as("SHA-512")
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        Self::import_state_as("SHA-512", state)
+    }
+
+    // SHA-384 and SHA-512/t keep their state in a `Sha512` and export it under their own label
+    pub(crate) fn export_state_as(&self, label: &str) -> Vec<u8> {
+        let hs: Vec<u8> = self.hs.iter().flat_map(|x| x.to_be_bytes()).collect();
+        encode_state(label, &hs, self.len, &self.buffer)
+    }
+
+    pub(crate) fn import_state_as(label: &str, state: &[u8]) -> Result<Self, HashError> {
+        let (hs, len, buffer) = decode_state(label, state, Self::DIGEST_SIZE, Self::BLOCK_SIZE)?;
+
+        let mut res = Self::from_state(
+            std::array::from_fn(|i| {
+                u64::from_be_bytes(
+                    hs[Self::WORD_SIZE * i..Self::WORD_SIZE * (i + 1)]
+                        .try_into()
+                        .unwrap(),
+                )
+            }),
+            len,
+        );
+        res.buffer.extend_from_slice(buffer);
+        Ok(res)
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.len += m.len();
+
+        let mut m = m;
+        if !self.buffer.is_empty() {
+            let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+            self.buffer.extend_from_slice(&m[..n]);
+            m = &m[n..];
+            if self.buffer.len() < Self::BLOCK_SIZE {
+                return;
             }
+            Self::compress(&mut self.hs, self.buffer.as_slice().try_into().unwrap());
+            self.buffer.clear();
         }
 
-        Ok(Sha512Digest::new(hs))
+        let mut blocks = m.chunks_exact(Self::BLOCK_SIZE);
+        for block in &mut blocks {
+            Self::compress(&mut self.hs, block.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalize(self) -> Sha512Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha512Digest {
+        assert!(bits < 8, "bits must be less than 8");
+
+        let mut buffer = self.buffer;
+        if bits > 0 {
+            buffer.push(last);
+        }
+
+        let mut hs = self.hs;
+        for block in Self::parse(Self::pad(buffer, self.len * 8 + bits)).unwrap() {
+            Self::compress(&mut hs, &block);
+        }
+
+        Sha512Digest::new(hs)
+    }
+
+    pub fn hash_iv(
+        m: Vec<u8>,
+        iv: [u64; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    ) -> Result<Sha512Digest, Vec<u8>> {
+        let mut hasher = Self::with_iv(iv);
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
     }
 
     pub fn hash(m: Vec<u8>) -> Result<Sha512Digest, Vec<u8>> {
         Self::hash_iv(m, Self::IV)
     }
+
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha512Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl HashAlgorithm for Sha512 {
+    type Output = Sha512Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha512::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha512::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha512::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha512::finalize_bits(self, last, bits))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use cavp_tester::cavp_test::CavpTest;
-
-    use crate::digest::HashDigest;
+    use crate::{cavp::check_vectors, digest::HashDigest, sha384::Sha384};
 
     use super::Sha512;
 
-    #[tokio::test]
-    async fn sha512() {
+    #[test]
+    fn sha512() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha512_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha512::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha512::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
+        check_vectors::<Sha512>("SHA512", &["ShortMsg", "LongMsg", "Monte"]);
+    }
 
-            assert!(t.test(md.trim().to_string()).is_ok());
+    #[test]
+    fn sha512_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha512::hash(m.clone()).unwrap().hexdigest();
+
+        for chunk_size in [1, 7, 127, 128, 129, 300] {
+            let mut hasher = Sha512::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha512_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha512::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha512::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha512::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
+
+        let mut hasher = Sha512::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha384::import_state(&state).is_err());
+        assert!(Sha512::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha512::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha512_bits() {
+        assert_eq!(
+            Sha512::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "ed86c3147879115f274d1e6053655e0cf77681a8e43b68968614e50e6b3e11dad9b4bf7fa14b453a100b2e5143acd719bb8664a64dbc53eef2e7306e7bf7373f"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha512::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "c8aeb725443c9ebe37fe4640569501e354e654af96bcf7cd4048f9cc30e2d07236f582edad7dd08856fe74061fd3c749f7e42b70aa53c3f12597a6b2d7f8db01"
+        );
+        assert_eq!(
+            Sha512::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha512::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha512::hash_bits(m, 1009).is_err());
     }
 }
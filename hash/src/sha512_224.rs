@@ -1,4 +1,9 @@
-use crate::{digest::HashDigest, sha512::Sha512};
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    sha512::Sha512,
+    util::words_to_bytes,
+};
 
 pub struct Sha512_224Digest {
     data: <Sha512_224Digest as HashDigest>::Digest,
@@ -12,11 +17,20 @@ impl Sha512_224Digest {
 
 impl HashDigest for Sha512_224Digest {
     type Digest = [u32; 7];
+    type Bytes = [u8; 28];
 
     fn digest(&self) -> Self::Digest {
         self.data
     }
 
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_be_bytes)
+    }
+
     fn hexdigest(&self) -> String {
         let mut res = String::new();
         for i in self.data {
@@ -26,13 +40,22 @@ impl HashDigest for Sha512_224Digest {
     }
 }
 
-pub struct Sha512_224 {}
+#[derive(Clone)]
+pub struct Sha512_224 {
+    inner: Sha512,
+}
+
+impl Default for Sha512_224 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha512_224 {
     // bytes
     const BLOCK_SIZE: usize = 128;
     const WORD_SIZE: usize = 8;
-    pub const DIGEST_SIZE: usize = 32;
+    pub const DIGEST_SIZE: usize = 28;
     const IV: [u64; 8] = [
         0x8C3D37C819544DA2,
         0x73E1996689DCD4D6,
@@ -44,8 +67,33 @@ impl Sha512_224 {
         0x1112E6AD91D692A1,
     ];
 
-    pub fn hash(m: Vec<u8>) -> Result<Sha512_224Digest, Vec<u8>> {
-        let sha512 = Sha512::hash_iv(m, Self::IV)?.digest();
+    pub fn new() -> Self {
+        Self {
+            inner: Sha512::with_iv(Self::IV),
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.inner.update(m);
+    }
+
+    pub fn export_state(&self) -> Vec<u8> {
+        self.inner.export_state_as("SHA-512/224")
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        Ok(Self {
+            inner: Sha512::import_state_as("SHA-512/224", state)?,
+        })
+    }
+
+    pub fn finalize(self) -> Sha512_224Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha512_224Digest {
+        let sha512 = self.inner.finalize_bits(last, bits).digest();
         let res = [
             (sha512[0] >> 32) as u32,
             (sha512[0] & 0xffffffff) as u32,
@@ -56,31 +104,115 @@ impl Sha512_224 {
             (sha512[3] >> 32) as u32,
         ];
 
-        Ok(Sha512_224Digest::new(res))
+        Sha512_224Digest::new(res)
+    }
+
+    pub fn hash(m: Vec<u8>) -> Result<Sha512_224Digest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha512_224Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl HashAlgorithm for Sha512_224 {
+    type Output = Sha512_224Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha512_224::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha512_224::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha512_224::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha512_224::finalize_bits(self, last, bits))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{digest::HashDigest, sha512_224::Sha512_224};
-    use cavp_tester::cavp_test::CavpTest;
+    use crate::{
+        cavp::check_vectors, digest::HashDigest, sha512_224::Sha512_224, sha512_256::Sha512_256,
+    };
 
-    #[tokio::test]
-    async fn sha512_224() {
+    #[test]
+    fn sha512_224() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha512_224_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha512_224::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha512_224::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
-
-            assert!(t.test(md.trim().to_string()).is_ok());
+        check_vectors::<Sha512_224>("SHA512_224", &["ShortMsg", "LongMsg", "Monte"]);
+    }
+
+    #[test]
+    fn sha512_224_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha512_224::hash(m.clone()).unwrap().hexdigest();
+
+        for chunk_size in [1, 7, 127, 128, 129, 300] {
+            let mut hasher = Sha512_224::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha512_224_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha512_224::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha512_224::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha512_224::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
+
+        let mut hasher = Sha512_224::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha512_256::import_state(&state).is_err());
+        assert!(Sha512_224::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha512_224::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha512_224_bits() {
+        assert_eq!(
+            Sha512_224::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "1cfbfd60e0c6f9100ea837fa2c577f0e0f266e22447885c99483c75b"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha512_224::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "f1d745254ed9045a8bf210ce7f9dc48e604ad0edae6c260af9c5dead"
+        );
+        assert_eq!(
+            Sha512_224::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha512_224::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha512_224::hash_bits(m, 1009).is_err());
     }
 }
@@ -1,4 +1,9 @@
-use crate::{digest::HashDigest, sha512::Sha512};
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    sha512::Sha512,
+    util::words_to_bytes,
+};
 
 pub struct Sha512_256Digest {
     data: <Sha512_256Digest as HashDigest>::Digest,
@@ -12,11 +17,20 @@ impl Sha512_256Digest {
 
 impl HashDigest for Sha512_256Digest {
     type Digest = [u64; 4];
+    type Bytes = [u8; 32];
 
     fn digest(&self) -> Self::Digest {
         self.data
     }
 
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u64::to_be_bytes)
+    }
+
     fn hexdigest(&self) -> String {
         let mut res = String::new();
         for i in self.data {
@@ -26,7 +40,16 @@ impl HashDigest for Sha512_256Digest {
     }
 }
 
-pub struct Sha512_256 {}
+#[derive(Clone)]
+pub struct Sha512_256 {
+    inner: Sha512,
+}
+
+impl Default for Sha512_256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Sha512_256 {
     // bytes
@@ -44,36 +67,143 @@ impl Sha512_256 {
         0x0EB72DDC81C52CA2,
     ];
 
+    pub fn new() -> Self {
+        Self {
+            inner: Sha512::with_iv(Self::IV),
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.inner.update(m);
+    }
+
+    pub fn export_state(&self) -> Vec<u8> {
+        self.inner.export_state_as("SHA-512/256")
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, HashError> {
+        Ok(Self {
+            inner: Sha512::import_state_as("SHA-512/256", state)?,
+        })
+    }
+
+    pub fn finalize(self) -> Sha512_256Digest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha512_256Digest {
+        let sha512 = self.inner.finalize_bits(last, bits);
+
+        Sha512_256Digest::new(sha512.digest()[0..4].try_into().unwrap())
+    }
+
     pub fn hash(m: Vec<u8>) -> Result<Sha512_256Digest, Vec<u8>> {
-        let sha512 = Sha512::hash_iv(m, Self::IV)?;
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
 
-        Ok(Sha512_256Digest::new(
-            sha512.digest()[0..4].try_into().unwrap(),
-        ))
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> Result<Sha512_256Digest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl HashAlgorithm for Sha512_256 {
+    type Output = Sha512_256Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha512_256::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha512_256::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha512_256::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha512_256::finalize_bits(self, last, bits))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{digest::HashDigest, sha512_256::Sha512_256};
-    use cavp_tester::cavp_test::CavpTest;
+    use crate::{
+        cavp::check_vectors, digest::HashDigest, sha512_224::Sha512_224, sha512_256::Sha512_256,
+    };
 
-    #[tokio::test]
-    async fn sha512_256() {
+    #[test]
+    fn sha512_256() {
         // NIST CAVP Testing (https://csrc.nist.gov/Projects/Cryptographic-Algorithm-Validation-Program/Secure-Hashing#shavs)
-        let test = CavpTest::new("test").unwrap();
-        test.download(cavp_tester::cavp_test::TestKind::SHA)
-            .await
-            .ok();
-
-        for t in test.sha512_256_byte_testvectors().unwrap() {
-            let md = if t.bit_len == 0 {
-                Sha512_256::hash(vec![]).unwrap().hexdigest()
-            } else {
-                Sha512_256::hash(t.as_bytes().msg).unwrap().hexdigest()
-            };
-
-            assert!(t.test(md.trim().to_string()).is_ok());
+        check_vectors::<Sha512_256>("SHA512_256", &["ShortMsg", "LongMsg", "Monte"]);
+    }
+
+    #[test]
+    fn sha512_256_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha512_256::hash(m.clone()).unwrap().hexdigest();
+
+        for chunk_size in [1, 7, 127, 128, 129, 300] {
+            let mut hasher = Sha512_256::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().hexdigest(), md);
+        }
+    }
+
+    #[test]
+    fn sha512_256_state() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let md = Sha512_256::hash(m.clone()).unwrap().hexdigest();
+
+        for split in [0, 1, 63, 64, 65, 127, 128, 129, 500, 1000] {
+            let mut hasher = Sha512_256::new();
+            hasher.update(&m[..split]);
+            let state = hasher.export_state();
+
+            let mut hasher = Sha512_256::import_state(&state).unwrap();
+            hasher.update(&m[split..]);
+            assert_eq!(hasher.finalize().hexdigest(), md);
         }
+
+        let mut hasher = Sha512_256::new();
+        hasher.update(&m[..10]);
+        let state = hasher.export_state();
+        assert!(Sha512_224::import_state(&state).is_err());
+        assert!(Sha512_256::import_state(&state[..state.len() - 1]).is_err());
+        assert!(Sha512_256::import_state(&[]).is_err());
+    }
+
+    #[test]
+    fn sha512_256_bits() {
+        assert_eq!(
+            Sha512_256::hash_bits(vec![0x98], 5).unwrap().hexdigest(),
+            "74d300e4e7191f2964eb4447f7dab99752c2c56a017f65af9ab3086c89f7ac56"
+        );
+        let m: Vec<u8> = (0..126).map(|i| i as u8).collect();
+        assert_eq!(
+            Sha512_256::hash_bits(m.clone(), 1003).unwrap().hexdigest(),
+            "4172cb781fa68e57b2bc8a504de574d72f80378ccf45fca4703f29ea38f4cc29"
+        );
+        assert_eq!(
+            Sha512_256::hash_bits(m.clone(), 1008).unwrap().hexdigest(),
+            Sha512_256::hash(m.clone()).unwrap().hexdigest()
+        );
+        assert!(Sha512_256::hash_bits(m, 1009).is_err());
     }
 }
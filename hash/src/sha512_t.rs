@@ -0,0 +1,290 @@
+use std::sync::OnceLock;
+
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    sha512::Sha512,
+};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+pub struct Sha512TDigest {
+    data: <Sha512TDigest as HashDigest>::Digest,
+}
+
+impl Sha512TDigest {
+    fn new(data: <Sha512TDigest as HashDigest>::Digest) -> Self {
+        Self { data }
+    }
+}
+
+impl HashDigest for Sha512TDigest {
+    type Digest = Vec<u8>;
+    type Bytes = Vec<u8>;
+
+    fn digest(&self) -> Self::Digest {
+        self.data.clone()
+    }
+
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        self.data
+    }
+
+    fn hexdigest(&self) -> String {
+        let mut res = String::new();
+        for i in &self.data {
+            res.push_str(&format!("{:0>2x?}", i));
+        }
+        res
+    }
+}
+
+// SHA-512/t IV generation function (FIPS 180-4 section 5.3.6):
+// SHA-512 with the IV xored by 0xa5a5...a5 over the ASCII string "SHA-512/t"
+pub fn iv(t: usize) -> Result<[u64; 8]> {
+    if t == 0 || t >= 512 || t == 384 {
+        return Err(HashError::ValueError(format!(
+            "SHA-512/t is defined for 0 < t < 512 except 384, got {}",
+            t
+        )));
+    }
+
+    let iv = Sha512::IV.map(|x| x ^ 0xa5a5a5a5a5a5a5a5);
+    let res = Sha512::hash_iv(format!("SHA-512/{}", t).into_bytes(), iv)
+        .map_err(|_| HashError::ValueError("cannot hash the IV string".into()))?;
+
+    Ok(res.digest())
+}
+
+// IVs of Sha512T, computed on first use and indexed by t
+static IVS: [OnceLock<[u64; 8]>; 512] = [const { OnceLock::new() }; 512];
+
+// SHA-512/t for a `T`-bit output. `Sha512_224` and `Sha512_256` are the fixed instances
+// with precomputed IVs; this computes the IV once per t, when the first hasher is created.
+#[derive(Clone)]
+pub struct Sha512T<const T: usize> {
+    inner: Sha512,
+}
+
+impl<const T: usize> Default for Sha512T<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const T: usize> Sha512T<T> {
+    // bytes
+    const BLOCK_SIZE: usize = 128;
+    pub const DIGEST_SIZE: usize = T.div_ceil(8);
+    const VALID: () = assert!(0 < T && T < 512 && T != 384, "invalid t for SHA-512/t");
+
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+
+        // iv(T) cannot fail, T is checked by VALID
+        let iv = IVS[T].get_or_init(|| iv(T).unwrap());
+        Self {
+            inner: Sha512::with_iv(*iv),
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.inner.update(m);
+    }
+
+    pub fn export_state(&self) -> Vec<u8> {
+        self.inner.export_state_as(&format!("SHA-512/{}", T))
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self> {
+        Ok(Self {
+            inner: Sha512::import_state_as(&format!("SHA-512/{}", T), state)?,
+        })
+    }
+
+    pub fn finalize(self) -> Sha512TDigest {
+        self.finalize_bits(0, 0)
+    }
+
+    // appends the `bits` (< 8) most significant bits of `last` to the message and finalizes
+    pub fn finalize_bits(self, last: u8, bits: usize) -> Sha512TDigest {
+        let mut res = self.inner.finalize_bits(last, bits).digest_u8();
+        res.truncate(Self::DIGEST_SIZE);
+        // the leftmost t bits are kept
+        if !T.is_multiple_of(8) {
+            *res.last_mut().unwrap() &= 0xff << (8 - T % 8);
+        }
+
+        Sha512TDigest::new(res)
+    }
+
+    pub fn hash(m: Vec<u8>) -> std::result::Result<Sha512TDigest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+
+    // hashes the first `bit_len` bits of `m`, which must be exactly ceil(bit_len / 8) bytes long
+    pub fn hash_bits(m: Vec<u8>, bit_len: usize) -> std::result::Result<Sha512TDigest, Vec<u8>> {
+        if m.len() != bit_len.div_ceil(8) {
+            return Err(m);
+        }
+
+        let mut hasher = Self::new();
+        hasher.update(&m[..bit_len / 8]);
+
+        Ok(hasher.finalize_bits(m.last().copied().unwrap_or(0), bit_len % 8))
+    }
+}
+
+impl<const T: usize> HashAlgorithm for Sha512T<T> {
+    type Output = Sha512TDigest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sha512T::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sha512T::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha512T::finalize(self)
+    }
+
+    fn finalize_bits(self, last: u8, bits: usize) -> Option<Self::Output> {
+        Some(Sha512T::finalize_bits(self, last, bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        digest::{HashAlgorithm, HashDigest},
+        sha512_224::Sha512_224,
+        sha512_256::Sha512_256,
+    };
+
+    use super::{iv, Sha512T};
+
+    #[test]
+    fn sha512_t_iv() {
+        // FIPS 180-4 section 5.3.6.1 and 5.3.6.2
+        assert_eq!(
+            iv(224).unwrap(),
+            [
+                0x8C3D37C819544DA2,
+                0x73E1996689DCD4D6,
+                0x1DFAB7AE32FF9C82,
+                0x679DD514582F9FCF,
+                0x0F6D2B697BD44DA8,
+                0x77E36F7304C48942,
+                0x3F9D85A86A1D36C8,
+                0x1112E6AD91D692A1,
+            ]
+        );
+        assert_eq!(
+            iv(256).unwrap(),
+            [
+                0x22312194FC2BF72C,
+                0x9F555FA3C84C64C2,
+                0x2393B86B6F53B151,
+                0x963877195940EABD,
+                0x96283EE2A88EFFE3,
+                0xBE5E1E2553863992,
+                0x2B0199FC2C85B8AA,
+                0x0EB72DDC81C52CA2,
+            ]
+        );
+
+        // the same construction in an independent implementation
+        assert_eq!(
+            iv(296).unwrap(),
+            [
+                0xE7B915384AF024DB,
+                0xB66F2F85F2BEC470,
+                0x788262EEA55D4219,
+                0x59F9ABC56546B72D,
+                0x0A2E7F556470B96E,
+                0x86017A302FE0170E,
+                0xF0C8A0BB0E73DC01,
+                0xD423D047A62FA054,
+            ]
+        );
+
+        assert!(iv(0).is_err());
+        assert!(iv(384).is_err());
+        assert!(iv(512).is_err());
+    }
+
+    #[test]
+    fn sha512_t() {
+        let m = b"abc".to_vec();
+        assert_eq!(
+            Sha512T::<224>::hash(m.clone()).unwrap().digest_u8(),
+            Sha512_224::hash(m.clone()).unwrap().digest_u8()
+        );
+        assert_eq!(
+            Sha512T::<256>::hash(m.clone()).unwrap().digest_u8(),
+            Sha512_256::hash(m.clone()).unwrap().digest_u8()
+        );
+
+        assert_eq!(Sha512T::<8>::hash(m.clone()).unwrap().hexdigest(), "c5");
+        assert_eq!(
+            Sha512T::<160>::hash(m.clone()).unwrap().hexdigest(),
+            "0a74fe1b43eecbea62182658da8a68b8acef25bf"
+        );
+        // t not a multiple of 8: the unused low bits of the last byte are zero
+        assert_eq!(
+            Sha512T::<100>::hash(m.clone()).unwrap().hexdigest(),
+            "36cc539a771da9ad5726499d80"
+        );
+        assert_eq!(
+            Sha512T::<511>::hash(m).unwrap().hexdigest(),
+            "71a80c6a46fbd2d092522f3a5d7750b9daa2c59f2ff05dfde25cd68e53317f4e79a080da3d4145b3fc2d8fe520cd787da4bb0165a90296a99a9a9b87994a087c"
+        );
+    }
+
+    #[test]
+    fn sha512_t_output_size() {
+        assert_eq!(<Sha512T<200> as HashAlgorithm>::OUTPUT_SIZE, 25);
+        assert_eq!(<Sha512T<100> as HashAlgorithm>::OUTPUT_SIZE, 13);
+        assert_eq!(Sha512_224::DIGEST_SIZE, 28);
+        assert_eq!(Sha512T::<200>::hash(vec![]).unwrap().digest_u8().len(), 25);
+    }
+
+    #[test]
+    fn sha512_t_state() {
+        let m: Vec<u8> = (0..300).map(|i| i as u8).collect();
+
+        let mut hasher = Sha512T::<200>::new();
+        hasher.update(&m[..150]);
+        let mut resumed = Sha512T::<200>::import_state(&hasher.export_state()).unwrap();
+        resumed.update(&m[150..]);
+        assert_eq!(
+            resumed.finalize().digest_u8(),
+            Sha512T::<200>::hash(m.clone()).unwrap().digest_u8()
+        );
+
+        // the same chaining words under another name are rejected
+        let mut hasher = Sha512_224::new();
+        hasher.update(&m[..150]);
+        let state = hasher.export_state();
+        assert!(Sha512T::<200>::import_state(&state).is_err());
+        assert!(Sha512_256::import_state(&state).is_err());
+        let mut resumed = Sha512T::<224>::import_state(&state).unwrap();
+        resumed.update(&m[150..]);
+        assert_eq!(
+            resumed.finalize().digest_u8(),
+            Sha512_224::hash(m).unwrap().digest_u8()
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    error::HashError,
+    sha256::Sha256,
+    sha512::Sha512,
+    util::ct_eq,
+};
+
+type Result<T> = std::result::Result<T, HashError>;
+
+const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// crypt's base64: each group of 3 bytes (fewer at the end), taken in `order`, is read as
+// a big endian integer and written 6 bits at a time starting from the least significant
+fn crypt_b64_encode(bytes: &[u8], order: &[usize]) -> String {
+    let mut res = String::new();
+    for group in order.chunks(3) {
+        let w = group
+            .iter()
+            .fold(0u32, |acc, &i| (acc << 8) | bytes[i] as u32);
+        for k in 0..=group.len() {
+            res.push(CRYPT_ALPHABET[(w >> (6 * k)) as usize & 0x3f] as char);
+        }
+    }
+    res
+}
+
+fn crypt_b64_decode(s: &str, order: &[usize]) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    if s.len() != (8 * order.len()).div_ceil(6) {
+        return None;
+    }
+
+    let mut res = vec![0; order.len()];
+    for (group, chars) in order.chunks(3).zip(s.chunks(4)) {
+        let mut w = 0u32;
+        for (k, c) in chars.iter().enumerate() {
+            let x = CRYPT_ALPHABET.iter().position(|x| x == c)? as u32;
+            w |= x << (6 * k);
+        }
+        // the unused high bits of the last character must be zero
+        if w >> (8 * group.len()) != 0 {
+            return None;
+        }
+        for (j, &i) in group.iter().rev().enumerate() {
+            res[i] = (w >> (8 * j)) as u8;
+        }
+    }
+    Some(res)
+}
+
+// SHA-crypt (https://www.akkadia.org/drepper/SHA-crypt.txt)
+fn sha_crypt<H: HashAlgorithm>(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let digest = |inputs: &[&[u8]]| {
+        let mut hasher = H::new();
+        for x in inputs {
+            hasher.update(x);
+        }
+        hasher.finalize().digest_u8()
+    };
+    // `x` repeated up to `len` bytes
+    let repeat =
+        |x: &[u8], len: usize| -> Vec<u8> { x.iter().copied().cycle().take(len).collect() };
+
+    let b = digest(&[password, salt, password]);
+
+    let mut hasher = H::new();
+    hasher.update(password);
+    hasher.update(salt);
+    hasher.update(&repeat(&b, password.len()));
+    // one input per bit of the password length, from the least significant
+    let mut n = password.len();
+    while n > 0 {
+        if n & 1 == 1 {
+            hasher.update(&b);
+        } else {
+            hasher.update(password);
+        }
+        n >>= 1;
+    }
+    let a = hasher.finalize().digest_u8();
+
+    let dp = digest(&vec![password; password.len()]);
+    let p = repeat(&dp, password.len());
+    let ds = digest(&vec![salt; 16 + a[0] as usize]);
+    let s = repeat(&ds, salt.len());
+
+    let mut c = a;
+    for i in 0..rounds {
+        let mut hasher = H::new();
+        if i % 2 == 1 {
+            hasher.update(&p);
+        } else {
+            hasher.update(&c);
+        }
+        if i % 3 != 0 {
+            hasher.update(&s);
+        }
+        if i % 7 != 0 {
+            hasher.update(&p);
+        }
+        if i % 2 == 1 {
+            hasher.update(&c);
+        } else {
+            hasher.update(&p);
+        }
+        c = hasher.finalize().digest_u8();
+    }
+    c
+}
+
+macro_rules! sha_crypt {
+    ($name:ident, $hash:ident, $id:expr, $order:expr) => {
+        // `rounds` is written into the encoded string only when it is given explicitly
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name {
+            rounds: Option<u32>,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(None)
+            }
+        }
+
+        impl $name {
+            const ID: &'static str = $id;
+            const ORDER: &'static [usize] = &$order;
+            pub const DEFAULT_ROUNDS: u32 = 5000;
+            pub const MIN_ROUNDS: u32 = 1000;
+            pub const MAX_ROUNDS: u32 = 999_999_999;
+            pub const MAX_SALT_SIZE: usize = 16;
+
+            // rounds outside of MIN_ROUNDS..=MAX_ROUNDS are clamped, as crypt(3) does
+            pub fn new(rounds: Option<u32>) -> Self {
+                Self {
+                    rounds: rounds.map(|x| x.clamp(Self::MIN_ROUNDS, Self::MAX_ROUNDS)),
+                }
+            }
+
+            pub fn rounds(&self) -> u32 {
+                self.rounds.unwrap_or(Self::DEFAULT_ROUNDS)
+            }
+
+            // salts longer than MAX_SALT_SIZE bytes are truncated
+            fn check_salt(salt: &[u8]) -> Result<&[u8]> {
+                let salt = &salt[..salt.len().min(Self::MAX_SALT_SIZE)];
+                // the salt is written as is in the encoded string
+                if salt.iter().any(|x| !x.is_ascii() || b"$:\n".contains(x)) {
+                    return Err(HashError::ValueError(
+                        "salt must be ASCII without '$', ':' or newlines".into(),
+                    ));
+                }
+                Ok(salt)
+            }
+
+            // raw digest, before the byte shuffle of the encoding
+            pub fn hash(&self, password: &[u8], salt: &[u8]) -> Result<Vec<u8>> {
+                let salt = Self::check_salt(salt)?;
+                Ok(sha_crypt::<$hash>(password, salt, self.rounds()))
+            }
+
+            // $<id>$rounds=<rounds>$<salt>$<hash>
+            pub fn hash_encoded(&self, password: &[u8], salt: &[u8]) -> Result<String> {
+                let salt = Self::check_salt(salt)?;
+                let digest = sha_crypt::<$hash>(password, salt, self.rounds());
+
+                let mut res = format!("${}$", Self::ID);
+                if let Some(rounds) = self.rounds {
+                    res += &format!("rounds={}$", rounds);
+                }
+                res.extend(salt.iter().map(|&x| x as char));
+                res.push('$');
+                res += &crypt_b64_encode(&digest, Self::ORDER);
+                Ok(res)
+            }
+
+            // parses an encoded string into the parameters, the salt and the raw digest
+            pub fn decode(encoded: &str) -> Result<(Self, Vec<u8>, Vec<u8>)> {
+                let err = || {
+                    HashError::ValueError(format!(
+                        "invalid ${}$ crypt string: {}",
+                        Self::ID,
+                        encoded
+                    ))
+                };
+
+                let fields: Vec<&str> = encoded.split('$').collect();
+                let (rounds, salt, digest) = match fields[..] {
+                    ["", id, salt, digest] if id == Self::ID => (None, salt, digest),
+                    ["", id, rounds, salt, digest] if id == Self::ID => {
+                        let rounds: u64 = rounds
+                            .strip_prefix("rounds=")
+                            .and_then(|x| x.parse().ok())
+                            .ok_or_else(err)?;
+                        (Some(rounds.min(u32::MAX as u64) as u32), salt, digest)
+                    }
+                    _ => return Err(err()),
+                };
+                if salt.len() > Self::MAX_SALT_SIZE {
+                    return Err(err());
+                }
+                let digest = crypt_b64_decode(digest, Self::ORDER).ok_or_else(err)?;
+
+                Ok((Self::new(rounds), salt.as_bytes().to_vec(), digest))
+            }
+
+            pub fn verify(encoded: &str, password: &[u8]) -> Result<bool> {
+                let (params, salt, digest) = Self::decode(encoded)?;
+
+                Ok(ct_eq(&params.hash(password, &salt)?, &digest))
+            }
+        }
+    };
+}
+
+// SHA-256-crypt ($5$) and SHA-512-crypt ($6$)
+sha_crypt!(
+    Sha256Crypt,
+    Sha256,
+    "5",
+    [
+        0, 10, 20, 21, 1, 11, 12, 22, 2, 3, 13, 23, 24, 4, 14, 15, 25, 5, 6, 16, 26, 27, 7, 17, 18,
+        28, 8, 9, 19, 29, 31, 30,
+    ]
+);
+sha_crypt!(
+    Sha512Crypt,
+    Sha512,
+    "6",
+    [
+        0, 21, 42, 22, 43, 1, 44, 2, 23, 3, 24, 45, 25, 46, 4, 47, 5, 26, 6, 27, 48, 28, 49, 7, 50,
+        8, 29, 9, 30, 51, 31, 52, 10, 53, 11, 32, 12, 33, 54, 34, 55, 13, 56, 14, 35, 15, 36, 57,
+        37, 58, 16, 59, 17, 38, 18, 39, 60, 40, 61, 19, 62, 20, 41, 63,
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{Sha256Crypt, Sha512Crypt};
+
+    #[test]
+    fn sha256_crypt() {
+        // https://www.akkadia.org/drepper/SHA-crypt.txt
+        let tests = [
+            (
+                None,
+                b"saltstring".as_slice(),
+                b"Hello world!".as_slice(),
+                "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+            ),
+            (
+                Some(10000),
+                b"saltstringsaltstring",
+                b"Hello world!",
+                "$5$rounds=10000$saltstringsaltst$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA",
+            ),
+            (
+                Some(5000),
+                b"toolongsaltstring",
+                b"This is just a test",
+                "$5$rounds=5000$toolongsaltstrin$Un/5jzAHMgOGZ5.mWJpuVolil07guHPvOW8mGRcvxa5",
+            ),
+            (
+                Some(10),
+                b"roundstoolow",
+                b"the minimum number is still observed",
+                "$5$rounds=1000$roundstoolow$yfvwcWrQ8l/K0DAWyuPMDNHpIVlTQebY9l/gL972bIC",
+            ),
+        ];
+        for (rounds, salt, password, encoded) in tests {
+            let crypt = Sha256Crypt::new(rounds);
+            assert_eq!(crypt.hash_encoded(password, salt).unwrap(), encoded);
+            assert!(Sha256Crypt::verify(encoded, password).unwrap());
+            assert!(!Sha256Crypt::verify(encoded, b"wrong").unwrap());
+        }
+    }
+
+    #[test]
+    fn sha512_crypt() {
+        // https://www.akkadia.org/drepper/SHA-crypt.txt
+        let tests = [
+            (
+                None,
+                b"saltstring".as_slice(),
+                b"Hello world!".as_slice(),
+                "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1",
+            ),
+            (
+                Some(10000),
+                b"saltstringsaltstring",
+                b"Hello world!",
+                "$6$rounds=10000$saltstringsaltst$OW1/O6BYHV6BcXZu8QVeXbDWra3Oeqh0sbHbbMCVNSnCM/UrjmM0Dp8vOuZeHBy/YTBmSK6H9qs/y3RnOaw5v.",
+            ),
+            (
+                Some(10),
+                b"roundstoolow",
+                b"the minimum number is still observed",
+                "$6$rounds=1000$roundstoolow$kUMsbe306n21p9R.FRkW3IGn.S9NPN0x50YhH1xhLsPuWGsUSklZt58jaTfF4ZEQpyUNGc0dqbpBYYBaHHrsX.",
+            ),
+        ];
+        for (rounds, salt, password, encoded) in tests {
+            let crypt = Sha512Crypt::new(rounds);
+            assert_eq!(crypt.hash_encoded(password, salt).unwrap(), encoded);
+            assert!(Sha512Crypt::verify(encoded, password).unwrap());
+            assert!(!Sha512Crypt::verify(encoded, b"wrong").unwrap());
+        }
+    }
+
+    #[test]
+    fn sha_crypt_decode() {
+        let (crypt, salt, digest) = Sha256Crypt::decode(
+            "$5$rounds=10000$saltstringsaltst$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA",
+        )
+        .unwrap();
+        assert_eq!(crypt.rounds(), 10000);
+        assert_eq!(salt, b"saltstringsaltst");
+        assert_eq!(digest.len(), 32);
+
+        // a longer password, and an empty one
+        assert!(Sha512Crypt::verify(
+            "$6$rounds=1400$anotherlongsalts$POfYwTEok97VWcjxIiSOjiykti.o/pQs.wPvMxQ6Fm7I6IoYN3CmLs66x9t0oSwbtEW7o7UmJEiDwGqd8p4ur1",
+            b"a very much longer text to encrypt.  This one even stretches over morethan one line."
+        )
+        .unwrap());
+        let encoded = Sha512Crypt::default().hash_encoded(b"", b"salt").unwrap();
+        assert!(Sha512Crypt::verify(&encoded, b"").unwrap());
+
+        for invalid in [
+            "$5$saltstring",
+            "$6$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+            "$5$round=10000$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+            "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc",
+            "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc!",
+            "$5$saltstringsaltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+        ] {
+            assert!(
+                Sha256Crypt::verify(invalid, b"Hello world!").is_err(),
+                "{}",
+                invalid
+            );
+        }
+
+        assert!(Sha256Crypt::default().hash(b"pw", b"salt$").is_err());
+        assert!(Sha256Crypt::default().hash(b"pw", b"sal\xff").is_err());
+        // a multibyte character cut at the 16-byte limit
+        assert!(Sha512Crypt::default()
+            .hash_encoded(b"pw", "saltsaltsaltsal\u{e9}".as_bytes())
+            .is_err());
+    }
+}
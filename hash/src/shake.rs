@@ -0,0 +1,153 @@
+use crate::{
+    digest::{ExtendableOutput, XofReader},
+    keccak::Sponge,
+};
+
+// Squeezing side of a Keccak-based XOF. `squeeze` can be called any number of times.
+#[derive(Clone)]
+pub struct KeccakReader {
+    sponge: Sponge,
+}
+
+impl KeccakReader {
+    pub(crate) fn new(sponge: Sponge) -> Self {
+        Self { sponge }
+    }
+}
+
+impl XofReader for KeccakReader {
+    fn squeeze(&mut self, out: &mut [u8]) {
+        self.sponge.squeeze(out);
+    }
+}
+
+macro_rules! shake {
+    ($name:ident, $rate:expr) => {
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl $name {
+            // bytes
+            const BLOCK_SIZE: usize = $rate;
+            const SUFFIX: u8 = 0x1f;
+
+            pub fn new() -> Self {
+                Self {
+                    sponge: Sponge::new(Self::BLOCK_SIZE, Self::SUFFIX),
+                }
+            }
+
+            // absorbs `m`
+            pub fn update(&mut self, m: &[u8]) {
+                self.sponge.absorb(m);
+            }
+
+            pub fn finalize_xof(self) -> KeccakReader {
+                KeccakReader::new(self.sponge)
+            }
+
+            pub fn hash(m: Vec<u8>, len: usize) -> Vec<u8> {
+                let mut hasher = Self::new();
+                hasher.update(&m);
+
+                hasher.finalize_xof().read(len)
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = KeccakReader;
+            const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+
+            fn new() -> Self {
+                $name::new()
+            }
+
+            fn update(&mut self, m: &[u8]) {
+                $name::update(self, m);
+            }
+
+            fn finalize_xof(self) -> Self::Reader {
+                $name::finalize_xof(self)
+            }
+        }
+    };
+}
+
+// SHAKE128 and SHAKE256 (FIPS 202)
+shake!(Shake128, 168);
+shake!(Shake256, 136);
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::XofReader, util::to_hex};
+
+    use super::{Shake128, Shake256};
+
+    fn messages() -> [Vec<u8>; 3] {
+        [vec![], b"abc".to_vec(), vec![0xa3; 200]]
+    }
+
+    #[test]
+    fn shake128() {
+        let mds = [
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26",
+            "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+            "131ab8d2b594946b9c81333f9bb6e0ce75c3b93104fa3469d3917457385da037",
+        ];
+        for (m, md) in messages().into_iter().zip(mds) {
+            assert_eq!(to_hex(&Shake128::hash(m, 32)), md);
+        }
+    }
+
+    #[test]
+    fn shake256() {
+        let mds = [
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+            "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+            "cd8a920ed141aa0407a22d59288652e9d9f1a7ee0c1e7c1ca699424da84a904d2d700caae7396ece96604440577da4f3aa22aeb8857f961c4cd8e06f0ae6610b",
+        ];
+        for (m, md) in messages().into_iter().zip(mds) {
+            assert_eq!(to_hex(&Shake256::hash(m, 64)), md);
+        }
+    }
+
+    #[test]
+    fn shake_squeeze() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        let mut hasher = Shake128::new();
+        for chunk in m.chunks(100) {
+            hasher.update(chunk);
+        }
+        let mut reader = hasher.finalize_xof();
+        let mut out = vec![];
+        for len in [1, 167, 168, 100, 64] {
+            out.extend(reader.read(len));
+        }
+        assert_eq!(out, Shake128::hash(m.clone(), 500));
+        assert_eq!(
+            to_hex(&out[468..]),
+            "025eadc1f23004fd57e457a8c3a8ab84c0b74fbdb8f08d67888dc769604dfda5"
+        );
+
+        let mut hasher = Shake256::new();
+        hasher.update(&m);
+        let mut reader = hasher.finalize_xof();
+        let mut out = [0u8; 500];
+        for chunk in out.chunks_mut(33) {
+            reader.squeeze(chunk);
+        }
+        assert_eq!(
+            to_hex(&out[468..]),
+            "915f93f8ea4696ce562702d4cba0e0e933f9c97d708788fd431b759d520c8176"
+        );
+    }
+}
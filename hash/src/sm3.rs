@@ -0,0 +1,298 @@
+use std::ops::Shl;
+
+use crate::{
+    digest::{HashAlgorithm, HashDigest},
+    util::words_to_bytes,
+};
+
+pub struct Sm3Digest {
+    data: <Sm3Digest as HashDigest>::Digest,
+}
+
+impl Sm3Digest {
+    fn new(data: <Sm3Digest as HashDigest>::Digest) -> Self {
+        Self { data }
+    }
+}
+
+impl HashDigest for Sm3Digest {
+    type Digest = [u32; 8];
+    type Bytes = [u8; 32];
+
+    fn digest(&self) -> Self::Digest {
+        self.data
+    }
+
+    fn digest_u8(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|x| x.to_be_bytes()).collect()
+    }
+
+    fn into_bytes(self) -> Self::Bytes {
+        words_to_bytes(&self.data, u32::to_be_bytes)
+    }
+
+    fn hexdigest(&self) -> String {
+        let mut res = String::new();
+        for i in self.data {
+            res.push_str(&format!("{:0>8x?}", i));
+        }
+        res
+    }
+}
+
+// SM3 (GB/T 32905-2016)
+#[derive(Clone)]
+pub struct Sm3 {
+    hs: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl Default for Sm3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sm3 {
+    // bytes
+    const BLOCK_SIZE: usize = 64;
+    const WORD_SIZE: usize = 4;
+    pub const DIGEST_SIZE: usize = 32;
+    const IV: [u32; Self::DIGEST_SIZE / Self::WORD_SIZE] = [
+        0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600, 0xa96f30bc, 0x163138aa, 0xe38dee4d,
+        0xb0fb0e4e,
+    ];
+    const T: [u32; 2] = [0x79cc4519, 0x7a879d8a];
+
+    #[inline(always)]
+    fn tj(j: usize) -> u32 {
+        if j < 16 {
+            Self::T[0].rotate_left(j as u32)
+        } else {
+            Self::T[1].rotate_left((j % 32) as u32)
+        }
+    }
+
+    #[inline(always)]
+    fn ff(x: u32, y: u32, z: u32, j: usize) -> u32 {
+        if j < 16 {
+            x ^ y ^ z
+        } else {
+            (x & y) | (x & z) | (y & z)
+        }
+    }
+
+    #[inline(always)]
+    fn gg(x: u32, y: u32, z: u32, j: usize) -> u32 {
+        if j < 16 {
+            x ^ y ^ z
+        } else {
+            (x & y) | ((!x) & z)
+        }
+    }
+
+    #[inline(always)]
+    fn p0(x: u32) -> u32 {
+        x ^ x.rotate_left(9) ^ x.rotate_left(17)
+    }
+
+    #[inline(always)]
+    fn p1(x: u32) -> u32 {
+        x ^ x.rotate_left(15) ^ x.rotate_left(23)
+    }
+
+    // `m` is the unprocessed tail of a message whose total length is `l` bytes
+    fn pad(m: Vec<u8>, l: usize) -> Vec<u8> {
+        let mods = (l + 1) % Self::BLOCK_SIZE;
+        let k = if mods > 56 {
+            Self::BLOCK_SIZE - (mods - 56)
+        } else {
+            56 - mods
+        };
+        let mut res = m;
+        res.push(0b10000000);
+        res.extend(vec![0x0_u8; k]);
+        res.extend_from_slice(&((l as u64).wrapping_mul(8)).to_be_bytes());
+        res
+    }
+
+    fn parse(m: Vec<u8>) -> Result<Vec<[u8; Self::BLOCK_SIZE]>, Vec<u8>> {
+        if !m.len().is_multiple_of(Self::BLOCK_SIZE) {
+            return Err(m);
+        }
+        let mut res = vec![];
+        for block in m.chunks(Self::BLOCK_SIZE) {
+            res.push(block.try_into().unwrap());
+        }
+        Ok(res)
+    }
+
+    fn compress(
+        hs: &mut [u32; Self::DIGEST_SIZE / Self::WORD_SIZE],
+        block: &[u8; Self::BLOCK_SIZE],
+    ) {
+        // message expansion: W_0..W_67 and W'_j = W_j ^ W_(j+4)
+        let wts: [u32; 68] = {
+            let mut wts = [0u32; 68];
+            for t in 0..68 {
+                if t < 16 {
+                    wts[t] = (block[4 * t] as u32).shl(24)
+                        | (block[4 * t + 1] as u32).shl(16)
+                        | (block[4 * t + 2] as u32).shl(8)
+                        | (block[4 * t + 3] as u32);
+                } else {
+                    wts[t] = Self::p1(wts[t - 16] ^ wts[t - 9] ^ wts[t - 3].rotate_left(15))
+                        ^ wts[t - 13].rotate_left(7)
+                        ^ wts[t - 6];
+                }
+            }
+            wts
+        };
+
+        let mut tmps = *hs;
+        for j in 0..64 {
+            let ss1 = tmps[0]
+                .rotate_left(12)
+                .wrapping_add(tmps[4])
+                .wrapping_add(Self::tj(j))
+                .rotate_left(7);
+            let ss2 = ss1 ^ tmps[0].rotate_left(12);
+            let tt1 = Self::ff(tmps[0], tmps[1], tmps[2], j)
+                .wrapping_add(tmps[3])
+                .wrapping_add(ss2)
+                .wrapping_add(wts[j] ^ wts[j + 4]);
+            let tt2 = Self::gg(tmps[4], tmps[5], tmps[6], j)
+                .wrapping_add(tmps[7])
+                .wrapping_add(ss1)
+                .wrapping_add(wts[j]);
+
+            tmps[3] = tmps[2];
+            tmps[2] = tmps[1].rotate_left(9);
+            tmps[1] = tmps[0];
+            tmps[0] = tt1;
+            tmps[7] = tmps[6];
+            tmps[6] = tmps[5].rotate_left(19);
+            tmps[5] = tmps[4];
+            tmps[4] = Self::p0(tt2);
+        }
+        for (h, x) in hs.iter_mut().zip(tmps) {
+            *h ^= x;
+        }
+    }
+
+    pub fn new() -> Self {
+        Self {
+            hs: Self::IV,
+            buffer: Vec::with_capacity(Self::BLOCK_SIZE),
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, m: &[u8]) {
+        self.len += m.len();
+
+        let mut m = m;
+        if !self.buffer.is_empty() {
+            let n = (Self::BLOCK_SIZE - self.buffer.len()).min(m.len());
+            self.buffer.extend_from_slice(&m[..n]);
+            m = &m[n..];
+            if self.buffer.len() < Self::BLOCK_SIZE {
+                return;
+            }
+            Self::compress(&mut self.hs, self.buffer.as_slice().try_into().unwrap());
+            self.buffer.clear();
+        }
+
+        let mut blocks = m.chunks_exact(Self::BLOCK_SIZE);
+        for block in &mut blocks {
+            Self::compress(&mut self.hs, block.try_into().unwrap());
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalize(self) -> Sm3Digest {
+        let mut hs = self.hs;
+        for block in Self::parse(Self::pad(self.buffer, self.len)).unwrap() {
+            Self::compress(&mut hs, &block);
+        }
+
+        Sm3Digest::new(hs)
+    }
+
+    pub fn hash(m: Vec<u8>) -> Result<Sm3Digest, Vec<u8>> {
+        let mut hasher = Self::new();
+        hasher.update(&m);
+
+        Ok(hasher.finalize())
+    }
+}
+
+impl HashAlgorithm for Sm3 {
+    type Output = Sm3Digest;
+    const BLOCK_SIZE: usize = Self::BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = Self::DIGEST_SIZE;
+
+    fn new() -> Self {
+        Sm3::new()
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        Sm3::update(self, m);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sm3::finalize(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::HashDigest, hmac::Hmac, util::to_hex};
+
+    use super::Sm3;
+
+    #[test]
+    fn sm3() {
+        // GB/T 32905-2016 appendix A
+        assert_eq!(
+            Sm3::hash(b"abc".to_vec()).unwrap().hexdigest(),
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+        );
+        assert_eq!(
+            Sm3::hash(b"abcd".repeat(16)).unwrap().hexdigest(),
+            "debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732"
+        );
+        assert_eq!(
+            Sm3::hash(vec![]).unwrap().hexdigest(),
+            "1ab21d8355cfa17f8e61194831e81a8f22bec8c728fefb747ed035eb5082aa2b"
+        );
+    }
+
+    #[test]
+    fn sm3_streaming() {
+        let m: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        for chunk_size in [1, 7, 63, 64, 65, 300] {
+            let mut hasher = Sm3::new();
+            for chunk in m.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finalize().hexdigest(),
+                "b38fc481302b502c3f2f6608d060c47c5b6bd8fd65e148b7cd3af4988245f48a"
+            );
+        }
+    }
+
+    #[test]
+    fn hmac_sm3() {
+        assert_eq!(
+            to_hex(&Hmac::<Sm3>::mac(
+                b"key",
+                b"The quick brown fox jumps over the lazy dog"
+            )),
+            "bd4a34077888162b210645b8ebf74b9af357303789357a27c7fc457244ebd398"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    cshake::{encode_string, right_encode, CShake128, CShake256},
+    digest::XofReader,
+    shake::KeccakReader,
+};
+
+macro_rules! tuplehash {
+    ($name:ident, $cshake:ident) => {
+        // Each element is absorbed with its length, so that ("ab", "c") and ("a", "bc")
+        // hash differently.
+        #[derive(Clone)]
+        pub struct $name {
+            inner: $cshake,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(b"")
+            }
+        }
+
+        impl $name {
+            pub fn new(customization: &[u8]) -> Self {
+                Self {
+                    inner: $cshake::new(b"TupleHash", customization),
+                }
+            }
+
+            // appends the next element of the tuple
+            pub fn push(&mut self, element: &[u8]) {
+                self.inner.update(&encode_string(element));
+            }
+
+            pub fn finalize(mut self, len: usize) -> Vec<u8> {
+                self.inner.update(&right_encode(len * 8));
+                self.inner.finalize_xof().read(len)
+            }
+
+            // TupleHashXOF
+            pub fn finalize_xof(mut self) -> KeccakReader {
+                self.inner.update(&right_encode(0));
+                self.inner.finalize_xof()
+            }
+
+            pub fn hash<T: AsRef<[u8]>>(tuple: &[T], len: usize, customization: &[u8]) -> Vec<u8> {
+                let mut hasher = Self::new(customization);
+                for element in tuple {
+                    hasher.push(element.as_ref());
+                }
+                hasher.finalize(len)
+            }
+        }
+    };
+}
+
+// TupleHash128 and TupleHash256 (SP 800-185 section 5)
+tuplehash!(TupleHash128, CShake128);
+tuplehash!(TupleHash256, CShake256);
+
+#[cfg(test)]
+mod tests {
+    use crate::{digest::XofReader, util::to_hex};
+
+    use super::{TupleHash128, TupleHash256};
+
+    fn tuple() -> [Vec<u8>; 3] {
+        [
+            vec![0x00, 0x01, 0x02],
+            vec![0x10, 0x11, 0x12, 0x13, 0x14, 0x15],
+            vec![0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28],
+        ]
+    }
+
+    #[test]
+    fn tuplehash() {
+        // SP 800-185 samples
+        let t = tuple();
+        assert_eq!(
+            to_hex(&TupleHash128::hash(&t[..2], 32, b"")),
+            "c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1"
+        );
+        assert_eq!(
+            to_hex(&TupleHash128::hash(&t[..2], 32, b"My Tuple App")),
+            "75cdb20ff4db1154e841d758e24160c54bae86eb8c13e7f5f40eb35588e96dfb"
+        );
+        assert_eq!(
+            to_hex(&TupleHash128::hash(&t, 32, b"My Tuple App")),
+            "e60f202c89a2631eda8d4c588ca5fd07f39e5151998deccf973adb3804bb6e84"
+        );
+        assert_eq!(
+            to_hex(&TupleHash256::hash(&t[..2], 64, b"")),
+            "cfb7058caca5e668f81a12a20a2195ce97a925f1dba3e7449a56f82201ec607311ac2696b1ab5ea2352df1423bde7bd4bb78c9aed1a853c78672f9eb23bbe194"
+        );
+
+        // the split of the data into elements matters
+        assert_ne!(
+            TupleHash128::hash(&[b"ab".as_slice(), b"c"], 32, b""),
+            TupleHash128::hash(&[b"a".as_slice(), b"bc"], 32, b"")
+        );
+    }
+
+    #[test]
+    fn tuplehash_xof() {
+        // SP 800-185 samples
+        let mut hasher = TupleHash128::new(b"");
+        for element in &tuple()[..2] {
+            hasher.push(element);
+        }
+        assert_eq!(
+            to_hex(&hasher.finalize_xof().read(32)),
+            "2f103cd7c32320353495c68de1a8129245c6325f6f2a3d608d92179c96e68488"
+        );
+    }
+}
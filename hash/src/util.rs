@@ -0,0 +1,192 @@
+use std::{sync::OnceLock, thread};
+
+use crate::error::HashError;
+
+// The number of threads to split work across by default. Queried once, since
+// `available_parallelism` reads cgroup files on Linux.
+pub(crate) fn available_threads() -> usize {
+    static THREADS: OnceLock<usize> = OnceLock::new();
+
+    *THREADS.get_or_init(|| thread::available_parallelism().map_or(1, |x| x.get()))
+}
+
+// Compares two byte strings in time that only depends on their lengths.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b) {
+        diff |= x ^ y;
+    }
+
+    std::hint::black_box(diff) == 0
+}
+
+// Serializes hash words into a fixed-size output with `to_bytes`, without allocating.
+pub(crate) fn words_to_bytes<T: Copy, const W: usize, const N: usize>(
+    words: &[T],
+    to_bytes: impl Fn(T) -> [u8; W],
+) -> [u8; N] {
+    let mut res = [0; N];
+    for (chunk, &x) in res.chunks_exact_mut(W).zip(words) {
+        chunk.copy_from_slice(&to_bytes(x));
+    }
+    res
+}
+
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+const B64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// base64 without padding, as in the PHC string format
+pub fn to_b64(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut x = [0u8; 3];
+        x[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from_be_bytes([0, x[0], x[1], x[2]]);
+        for i in 0..=chunk.len() {
+            res.push(B64_ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+        }
+    }
+    res
+}
+
+pub fn from_b64(s: &str) -> Option<Vec<u8>> {
+    // a single character left over cannot encode a byte
+    if s.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut res = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let x = B64_ALPHABET.iter().position(|x| x == c)? as u32;
+            n |= x << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        // the unused low bits must be zero, so that every string has a unique decoding
+        if bytes[chunk.len()..].iter().any(|&x| x != 0) {
+            return None;
+        }
+        res.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(res)
+}
+
+// Serialized midstate of a Merkle-Damgard hash:
+// label length (1 byte) || label || chaining value || length in bytes (8 bytes, big endian) || buffer.
+// The label names the algorithm so that, e.g., a SHA-224 state is not resumed as SHA-256.
+pub(crate) fn encode_state(label: &str, hs: &[u8], len: usize, buffer: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(1 + label.len() + hs.len() + 8 + buffer.len());
+    res.push(label.len() as u8);
+    res.extend_from_slice(label.as_bytes());
+    res.extend_from_slice(hs);
+    res.extend_from_slice(&(len as u64).to_be_bytes());
+    res.extend_from_slice(buffer);
+    res
+}
+
+// inverse of `encode_state`. Returns the chaining value, the length and the buffered bytes.
+pub(crate) fn decode_state<'a>(
+    label: &str,
+    state: &'a [u8],
+    hs_size: usize,
+    block_size: usize,
+) -> Result<(&'a [u8], usize, &'a [u8]), HashError> {
+    let err = |e: &str| {
+        Err(HashError::ValueError(format!(
+            "invalid {} state: {}",
+            label, e
+        )))
+    };
+
+    let Some((&n, rest)) = state.split_first() else {
+        return err("empty");
+    };
+    if rest.len() < n as usize || &rest[..n as usize] != label.as_bytes() {
+        return err(&format!(
+            "the state is for {}",
+            String::from_utf8_lossy(&rest[..rest.len().min(n as usize)])
+        ));
+    }
+    let rest = &rest[n as usize..];
+    if rest.len() < hs_size + 8 {
+        return err("too short");
+    }
+
+    let (hs, rest) = rest.split_at(hs_size);
+    let (len, buffer) = rest.split_at(8);
+    let Ok(len) = usize::try_from(u64::from_be_bytes(len.try_into().unwrap())) else {
+        return err("length overflows usize");
+    };
+    // the hashers pad with the length in bits
+    if len > usize::MAX / 8 {
+        return err("the length in bits overflows");
+    }
+    if buffer.len() != len % block_size {
+        return err("the buffer does not match the length");
+    }
+
+    Ok((hs, len, buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ct_eq, decode_state, encode_state, from_b64, from_hex, to_b64, to_hex};
+
+    #[test]
+    fn util() {
+        assert!(ct_eq(b"", b""));
+        assert!(ct_eq(b"abc", b"abc"));
+        assert!(!ct_eq(b"abc", b"abd"));
+        assert!(!ct_eq(b"abc", b"ab"));
+
+        assert_eq!(to_hex(&[0x00, 0x1f, 0xab]), "001fab");
+        assert_eq!(from_hex("001fAB"), Some(vec![0x00, 0x1f, 0xab]));
+        assert_eq!(from_hex("001"), None);
+        assert_eq!(from_hex("zz"), None);
+
+        assert_eq!(to_b64(b""), "");
+        assert_eq!(to_b64(b"f"), "Zg");
+        assert_eq!(to_b64(b"fo"), "Zm8");
+        assert_eq!(to_b64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(to_b64(&[0xfb, 0xff]), "+/8");
+        assert_eq!(from_b64("Zm9vYg"), Some(b"foob".to_vec()));
+        assert_eq!(from_b64("+/8"), Some(vec![0xfb, 0xff]));
+        assert_eq!(from_b64("Zm9vY"), None);
+        assert_eq!(from_b64("Zh"), None);
+        assert_eq!(from_b64("Zg=="), None);
+    }
+
+    #[test]
+    fn util_state() {
+        // the encoded state with another length
+        let state =
+            |len: u64| [&encode_state("X", &[1, 2], 0, b"")[..4], &len.to_be_bytes()].concat();
+
+        let valid = state(128);
+        let (hs, len, buffer) = decode_state("X", &valid, 2, 64).unwrap();
+        assert_eq!((hs, len, buffer), (&[1, 2][..], 128, &[][..]));
+        assert!(decode_state("Y", &state(128), 2, 64).is_err());
+        assert!(decode_state("X", &state(130), 2, 64).is_err());
+        assert!(decode_state("X", &state((usize::MAX / 8 + 1) as u64), 2, 64).is_err());
+        assert!(decode_state("X", &state(u64::MAX - 63), 2, 64).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use hash::{digest::HashDigest, sha1::Sha1};
-use rug::{integer::IntegerExt64, Integer};
+use rug::integer::IntegerExt64;
 
 use crate::{
     error::Pkcs1Error,
@@ -23,13 +23,7 @@ impl RsaOaep {
             let c = i2osp(i.into(), 4).unwrap();
             let mut seed = mgf_seed.clone();
             seed.extend(c);
-            let digest = Sha1::hash(seed).unwrap().digest();
-            let res: Vec<u8> = digest
-                .map(|x| i2osp(Integer::from(x), 4).unwrap())
-                .into_iter()
-                .flatten()
-                .collect();
-            t.extend(res);
+            t.extend(Sha1::hash(seed).unwrap().digest_u8());
         }
 
         Ok(t[..mask_len].to_vec())
@@ -55,13 +49,7 @@ impl RsaOaep {
             return Err(Pkcs1Error::ValueError("seed len is invalid".into()));
         }
 
-        let lhash: Vec<u8> = Sha1::hash(l)
-            .unwrap()
-            .digest()
-            .map(|x| i2osp(Integer::from(x), 4).unwrap())
-            .into_iter()
-            .flatten()
-            .collect();
+        let lhash = Sha1::hash(l).unwrap().digest_u8();
         println!("lhash: {:x?}", lhash);
         println!("len: {}", lhash.len());
 